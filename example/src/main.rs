@@ -13,12 +13,14 @@ pub struct Item {
 #[derive(Default, BinaryStruct)]
 #[binary_struct(bit_order = shua_struct::Lsb0)]
 pub struct Inventory {
+    #[binary_field(calc = "self.items.len() as u8", verify_calc)]
     pub max_slots: u8,
     #[binary_field(size_func = get_actual_slots)]
     pub items: Vec<Item>,
 }
 
 impl Inventory {
+    #[allow(clippy::needless_return)]
     fn get_actual_slots(&self) -> usize {
         return self.max_slots as usize;
     }
@@ -45,9 +47,12 @@ pub struct Skill {
 }
 
 #[derive(Default, BinaryStruct)]
-#[binary_struct(bit_order = shua_struct::Lsb0)]
+#[binary_struct(bit_order = shua_struct::Lsb0, version = version)]
 pub struct GameSave {
     pub version: u16,
+    #[binary_field(since = 2)]
+    pub play_time: u32,
+    #[binary_field(calc = "self.players.len() as u8")]
     pub player_count: u8,
     #[binary_field(size_field = player_count)]
     pub players: Vec<Player>,
@@ -58,6 +63,7 @@ pub struct GameSave {
 fn main() {
     let game_save = GameSave {
         version: 1,
+        play_time: 0,
         player_count: 2,
         options: [true, false, true, false, true, true],
         players: vec![
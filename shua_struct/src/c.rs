@@ -0,0 +1,613 @@
+//! C struct layouts: the padding `#[binary_struct(repr_c)]` inserts, and C headers
+//! for derived types, generated from their [`Schema`].
+//!
+//! With `repr_c` every field starts where a C compiler would put a member of its
+//! type: at a multiple of its [`BinaryField::C_ALIGN`], or for fields with a `bits`
+//! option, as a bitfield that does not straddle a unit of its type. The struct ends
+//! on a multiple of its widest alignment. `cond`, `since`/`until`, `size_field`,
+//! `size_func` and `offset_field`/`seek` are rejected, as C structs have one layout.
+//!
+//! [`header`] declares matching structs with `<stdint.h>` types, bitfields for
+//! `bits` options and `bool`s, and explicit members for `pad_*` and `Reserved`
+//! bits, followed by `_Static_assert(sizeof(...))` checks. Bitfield and byte order
+//! follow the GCC/Clang ABI of little-endian targets for `Lsb0` and big-endian
+//! targets for `Msb0`. Structs whose fields do not sit at C's natural positions
+//! are declared `__attribute__((packed))`. What C cannot express (containers of
+//! bitfields, variable-length and conditional fields) is kept as raw bytes or a
+//! comment.
+//!
+//! ```ignore
+//! #[derive(BinaryStruct, Default)]
+//! #[binary_struct(repr_c)]
+//! pub struct Packet {
+//!     pub kind: u8,
+//!     pub len: u32, // 3 bytes of padding before
+//! }
+//!
+//! std::fs::write("packet.h", shua_struct::c::header(&Packet::schema()))?;
+//! ```
+//!
+//! [`BinaryField::C_ALIGN`]: crate::BinaryField::C_ALIGN
+
+use crate::kaitai::type_id;
+use crate::schema::{FieldSchema, FloatKind, Layout, Position, Schema, item_width};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "bool", "case", "char", "const", "default", "double", "float", "goto", "inline", "int",
+    "long", "register", "restrict", "short", "signed", "sizeof", "switch", "typedef", "union",
+    "unsigned", "void", "volatile",
+];
+
+/// Where C places a member at bit `offset` of its struct, given its alignment in bits.
+///
+/// With `bits`, the member is a bitfield of that width in units of `align` bits,
+/// placed at `offset` unless it would straddle two units.
+pub fn place(offset: usize, align: usize, bits: Option<usize>) -> usize {
+    let align = align.max(1);
+    match bits {
+        Some(bits) if bits > 0 && offset / align == (offset + bits - 1) / align => offset,
+        _ => offset.next_multiple_of(align),
+    }
+}
+
+/// Records the padding of a `repr_c` struct in its fields' `pad_before`, and its
+/// trailing padding in the last field's `pad_after`.
+///
+/// `aligns` holds each field's C alignment and `align` the struct's. Padding after
+/// a field of unknown width is not recorded.
+#[doc(hidden)]
+pub fn pad_fields(fields: &mut [FieldSchema], aligns: &[usize], align: usize) {
+    let mut offset = 0;
+    let mut last = None;
+    for (i, (field, &field_align)) in fields.iter_mut().zip(aligns).enumerate() {
+        if field.presence.skip {
+            continue;
+        }
+        offset += field.pad_before;
+        let start = place(offset, field_align, field.bits);
+        field.pad_before += start - offset;
+        let Some(width) = field
+            .layout
+            .sized_bit_width(field.bits, &field.aligns, &field.sizes)
+        else {
+            return;
+        };
+        let width = match field.align.filter(|&align| align != 0) {
+            Some(align) => width.next_multiple_of(align),
+            None => width,
+        };
+        offset = start + width + field.pad_after;
+        last = Some(i);
+    }
+    if let Some(last) = last {
+        fields[last].pad_after += offset.next_multiple_of(align) - offset;
+    }
+}
+
+/// Renders `schema` as a C header, with every struct nested in it declared first.
+pub fn header(schema: &Schema) -> String {
+    let mut c = Header {
+        structs: Vec::new(),
+        done: Vec::new(),
+    };
+    c.struct_def(schema);
+    let guard = format!("{}_H", type_id(&schema.name).to_ascii_uppercase());
+    let (order, endian) = if schema.bit_order == "Msb0" {
+        ("__ORDER_BIG_ENDIAN__", "big")
+    } else {
+        ("__ORDER_LITTLE_ENDIAN__", "little")
+    };
+
+    let mut out = format!(
+        "/* C declarations for `{}`, generated by shua_struct. */\n\n",
+        schema.name
+    );
+    let _ = write!(
+        out,
+        "#ifndef {guard}\n#define {guard}\n\n#include <stdbool.h>\n#include <stdint.h>\n\n\
+         #if defined(__BYTE_ORDER__) && __BYTE_ORDER__ != {order}\n\
+         #error \"these structs are laid out for {endian}-endian targets\"\n#endif\n",
+    );
+    for def in &c.structs {
+        out.push('\n');
+        out.push_str(def);
+    }
+    let _ = writeln!(out, "\n#endif /* {} */", guard);
+    out
+}
+
+/// Size and alignment in bits of a declared struct.
+#[derive(Clone, Copy)]
+struct Shape {
+    /// `sizeof` in bits, if the layout is fixed.
+    size: Option<usize>,
+    align: usize,
+}
+
+/// A field as a C member.
+enum Member {
+    /// Declared as `ty name dims`, or `ty name : bits` for bitfields.
+    Typed {
+        ty: String,
+        dims: String,
+        bitfield: Option<usize>,
+        width: usize,
+        /// Alignment in bits, the unit size for bitfields.
+        align: usize,
+        note: String,
+    },
+    /// Kept as raw bits, if their width is known.
+    Raw { width: Option<usize>, note: String },
+}
+
+/// Struct members being declared.
+struct Body {
+    lines: Vec<String>,
+    /// Bit offset of the next member, while it is known.
+    offset: Option<usize>,
+    /// Whether a C compiler would put every member at its offset without `packed`.
+    natural: bool,
+    align: usize,
+    pads: usize,
+}
+
+impl Body {
+    /// Adds `n` unnamed padding bits as bitfields up to a byte boundary, then bytes.
+    fn padding(&mut self, n: usize) {
+        self.bits(None, n, "");
+    }
+
+    /// Adds `n` bits as a byte array named `name` where they are whole bytes, or
+    /// as unnamed bitfields.
+    fn bits(&mut self, name: Option<&str>, mut n: usize, note: &str) {
+        if n == 0 {
+            return;
+        }
+        let phase = self.offset.map_or(0, |offset| offset % 8);
+        if let Some(offset) = &mut self.offset {
+            *offset += n;
+        }
+        if let Some(name) = name.filter(|_| phase == 0 && n.is_multiple_of(8)) {
+            self.lines.push(format!(
+                "uint8_t {}[{}];{}",
+                ident(name),
+                n / 8,
+                comment(note)
+            ));
+            return;
+        }
+        if let Some(name) = name {
+            self.lines
+                .push(format!("/* {}: {} bits{} */", name, n, note_suffix(note)));
+        }
+        if phase != 0 {
+            let head = (8 - phase).min(n);
+            self.lines.push(format!("uint8_t : {};", head));
+            n -= head;
+        }
+        if n >= 8 {
+            self.lines
+                .push(format!("uint8_t _pad{}[{}];", self.pads, n / 8));
+            self.pads += 1;
+        }
+        if !n.is_multiple_of(8) {
+            self.lines.push(format!("uint8_t : {};", n % 8));
+        }
+    }
+}
+
+struct Header {
+    /// Struct definitions, each before its first use.
+    structs: Vec<String>,
+    done: Vec<(String, Shape)>,
+}
+
+impl Header {
+    /// Declares `schema` once, returning its shape.
+    fn struct_def(&mut self, schema: &Schema) -> Shape {
+        if let Some((_, shape)) = self.done.iter().find(|(name, _)| *name == schema.name) {
+            return *shape;
+        }
+        // Recursive structs have no fixed size.
+        self.done.push((
+            schema.name.clone(),
+            Shape {
+                size: None,
+                align: 8,
+            },
+        ));
+        let body = self.body(&schema.fields);
+        let shape = shape(&body);
+        if let Some(entry) = self.done.iter_mut().find(|(name, _)| *name == schema.name) {
+            entry.1 = shape;
+        }
+
+        let mut def = String::new();
+        for line in schema.doc.lines() {
+            let _ = writeln!(def, "/* {} */", escape(line).trim_end());
+        }
+        let packed = if body.natural {
+            ""
+        } else {
+            "__attribute__((packed)) "
+        };
+        let _ = writeln!(def, "struct {}{} {{", packed, schema.name);
+        for line in &body.lines {
+            let _ = writeln!(def, "    {}", line);
+        }
+        def.push_str("};\n");
+        if let Some(size) = shape.size {
+            let _ = writeln!(
+                def,
+                "_Static_assert(sizeof(struct {}) == {}, \"struct {} must be {} bytes\");",
+                schema.name,
+                size / 8,
+                schema.name,
+                size / 8
+            );
+        }
+        self.structs.push(def);
+        shape
+    }
+
+    fn body(&mut self, fields: &[FieldSchema]) -> Body {
+        let mut body = Body {
+            lines: Vec::new(),
+            offset: Some(0),
+            natural: true,
+            align: 8,
+            pads: 0,
+        };
+        for field in fields {
+            if field.presence.skip {
+                continue;
+            }
+            if field.presence.is_conditional() || field.position != Position::Sequential {
+                let why = if field.presence.is_conditional() {
+                    "conditional"
+                } else {
+                    "stored out of line"
+                };
+                body.lines
+                    .push(format!("/* {}: {}, not declared */", field.name, why));
+                body.offset = None;
+                continue;
+            }
+            body.padding(field.pad_before);
+            for line in field.doc.lines() {
+                body.lines
+                    .push(format!("/* {} */", escape(line).trim_end()));
+            }
+            let member = if field.size == crate::schema::SizeSource::None {
+                self.member(&field.layout, field.bits, &field.aligns, &field.sizes)
+            } else {
+                Member::Raw {
+                    width: None,
+                    note: "variable length".into(),
+                }
+            };
+            // Only bitfields may start within a byte, even in packed structs.
+            let member = match member {
+                Member::Typed {
+                    ty,
+                    dims,
+                    bitfield: None,
+                    width,
+                    align,
+                    note,
+                } if body.offset.is_some_and(|offset| !offset.is_multiple_of(8)) => {
+                    if dims.is_empty() && ty.ends_with("_t") {
+                        Member::Typed {
+                            ty,
+                            dims,
+                            bitfield: Some(width),
+                            width,
+                            align,
+                            note,
+                        }
+                    } else {
+                        Member::Raw {
+                            width: Some(width),
+                            note: "starts within a byte".into(),
+                        }
+                    }
+                }
+                member => member,
+            };
+            let width = match member {
+                Member::Typed {
+                    ty,
+                    dims,
+                    bitfield,
+                    width,
+                    align,
+                    note,
+                } => {
+                    if let Some(offset) = body.offset
+                        && place(offset, align, bitfield) != offset
+                    {
+                        body.natural = false;
+                    }
+                    body.align = body.align.max(align);
+                    let bitfield = bitfield.map_or(String::new(), |bits| format!(" : {}", bits));
+                    body.lines.push(format!(
+                        "{} {}{}{};{}",
+                        ty,
+                        ident(&field.name),
+                        dims,
+                        bitfield,
+                        comment(&note)
+                    ));
+                    body.offset = body.offset.map(|offset| offset + width);
+                    Some(width)
+                }
+                Member::Raw {
+                    width: Some(width),
+                    note,
+                } => {
+                    body.bits(Some(&field.name), width, &note);
+                    Some(width)
+                }
+                Member::Raw { width: None, note } => {
+                    body.lines
+                        .push(format!("/* {}: {}, not declared */", field.name, note));
+                    body.offset = None;
+                    None
+                }
+            };
+            if let Some(align) = field.align.filter(|&align| align != 0) {
+                match width {
+                    Some(width) => body.padding(width.next_multiple_of(align) - width),
+                    None => body.offset = None,
+                }
+            }
+            body.padding(field.pad_after);
+        }
+        match body.offset {
+            Some(offset) if offset.is_multiple_of(body.align) => {}
+            _ => body.natural = false,
+        }
+        body
+    }
+
+    fn member(
+        &mut self,
+        layout: &Layout,
+        bits: Option<usize>,
+        aligns: &[usize],
+        sizes: &[usize],
+    ) -> Member {
+        match layout {
+            Layout::Bool => Member::Typed {
+                ty: "bool".into(),
+                dims: String::new(),
+                bitfield: Some(1),
+                width: 1,
+                align: 8,
+                note: String::new(),
+            },
+            Layout::Int { signed, bits: full }
+            | Layout::Fixed {
+                signed, bits: full, ..
+            } => {
+                let note = match layout {
+                    Layout::Fixed { frac, .. } => format!("fixed point, {} fractional bits", frac),
+                    _ => String::new(),
+                };
+                int_member(*signed, *full, bits, note)
+            }
+            Layout::Float {
+                bits: 32,
+                kind: FloatKind::Ieee,
+            } => plain("float", 32),
+            Layout::Float {
+                bits: 64,
+                kind: FloatKind::Ieee,
+            } => plain("double", 64),
+            Layout::Float { bits: width, kind } => {
+                int_member(false, *width, None, format!("{} bits", kind.name(*width)))
+            }
+            Layout::Reserved { bits: width, .. } => Member::Raw {
+                width: Some(*width),
+                note: "reserved".into(),
+            },
+            Layout::Flags { repr, flags } => {
+                let mut member = self.member(repr, bits, aligns, sizes);
+                let list = flags
+                    .iter()
+                    .map(|(name, value)| format!("{} = 0x{:x}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match &mut member {
+                    Member::Typed { note, .. } | Member::Raw { note, .. } => *note = list,
+                }
+                member
+            }
+            Layout::Array { item, .. } | Layout::Seq { item } => {
+                let len = match layout {
+                    Layout::Array { len, .. } => Some(*len),
+                    _ => sizes.first().copied().filter(|&size| size != 0),
+                };
+                let Some(len) = len else {
+                    return Member::Raw {
+                        width: None,
+                        note: "variable length".into(),
+                    };
+                };
+                let element = self.member(
+                    item,
+                    bits,
+                    aligns.get(1..).unwrap_or_default(),
+                    sizes.get(1..).unwrap_or_default(),
+                );
+                match element {
+                    Member::Typed {
+                        ty,
+                        dims,
+                        bitfield: None,
+                        width,
+                        align,
+                        note,
+                    } if item_width(item, bits, aligns, sizes) == Some(width) => Member::Typed {
+                        ty,
+                        dims: format!("[{}]{}", len, dims),
+                        bitfield: None,
+                        width: len * width,
+                        align,
+                        note,
+                    },
+                    _ => Member::Raw {
+                        width: layout.sized_bit_width(bits, aligns, sizes),
+                        note: "elements C cannot lay out".into(),
+                    },
+                }
+            }
+            Layout::Tuple(items) => {
+                let fields = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| FieldSchema {
+                        name: format!("_{}", i),
+                        doc: String::new(),
+                        rust_type: String::new(),
+                        wire_type: None,
+                        layout: item.clone(),
+                        bits,
+                        align: None,
+                        aligns: aligns.to_vec(),
+                        sizes: sizes.to_vec(),
+                        size: Default::default(),
+                        pad_before: 0,
+                        pad_after: 0,
+                        presence: Default::default(),
+                        position: Position::Sequential,
+                        calc: None,
+                    })
+                    .collect::<Vec<_>>();
+                let body = self.body(&fields);
+                let shape = shape(&body);
+                match (shape.size, body.offset) {
+                    (Some(size), Some(width)) if size == width => Member::Typed {
+                        ty: format!(
+                            "struct {}{{ {} }}",
+                            if body.natural {
+                                ""
+                            } else {
+                                "__attribute__((packed)) "
+                            },
+                            body.lines.join(" ")
+                        ),
+                        dims: String::new(),
+                        bitfield: None,
+                        width,
+                        align: shape.align,
+                        note: String::new(),
+                    },
+                    _ => Member::Raw {
+                        width: body.offset,
+                        note: "tuple".into(),
+                    },
+                }
+            }
+            Layout::Struct(schema) => {
+                let shape = self.struct_def(schema);
+                match (shape.size, schema.bit_width()) {
+                    (Some(size), Some(width)) if size == width => Member::Typed {
+                        ty: format!("struct {}", schema.name),
+                        dims: String::new(),
+                        bitfield: None,
+                        width,
+                        align: shape.align,
+                        note: String::new(),
+                    },
+                    (_, width) => Member::Raw {
+                        width,
+                        note: format!("struct {}", schema.name),
+                    },
+                }
+            }
+            Layout::Opaque => Member::Raw {
+                width: None,
+                note: "no layout".into(),
+            },
+        }
+    }
+}
+
+/// `sizeof` and alignment of a declared struct body.
+fn shape(body: &Body) -> Shape {
+    if body.natural {
+        Shape {
+            size: body.offset,
+            align: body.align,
+        }
+    } else {
+        Shape {
+            size: body.offset.map(|offset| offset.next_multiple_of(8)),
+            align: 8,
+        }
+    }
+}
+
+fn plain(ty: &str, width: usize) -> Member {
+    Member::Typed {
+        ty: ty.into(),
+        dims: String::new(),
+        bitfield: None,
+        width,
+        align: width,
+        note: String::new(),
+    }
+}
+
+/// An integer of `full` bits, a bitfield where `bits` narrows it.
+fn int_member(signed: bool, full: usize, bits: Option<usize>, note: String) -> Member {
+    let width = bits.unwrap_or(full);
+    if !matches!(full, 8 | 16 | 32 | 64) || width > full {
+        return Member::Raw {
+            width: Some(width),
+            note,
+        };
+    }
+    Member::Typed {
+        ty: format!("{}int{}_t", if signed { "" } else { "u" }, full),
+        dims: String::new(),
+        bitfield: (width != full).then_some(width),
+        width,
+        align: full,
+        note,
+    }
+}
+
+/// A member name that is not a C keyword.
+fn ident(name: &str) -> String {
+    if C_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.into()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace("*/", "* /")
+}
+
+fn comment(note: &str) -> String {
+    if note.is_empty() {
+        String::new()
+    } else {
+        format!(" /* {} */", escape(note))
+    }
+}
+
+fn note_suffix(note: &str) -> String {
+    if note.is_empty() {
+        String::new()
+    } else {
+        format!(", {}", escape(note))
+    }
+}
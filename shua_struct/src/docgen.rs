@@ -0,0 +1,286 @@
+//! Markdown documentation for derived types, generated from their [`Schema`].
+//!
+//! ```ignore
+//! std::fs::write("docs/packet.md", shua_struct::docgen::markdown(&Packet::schema()))?;
+//! ```
+
+use crate::dump::{BitRange, bit_diagram};
+use crate::schema::{FieldSchema, Layout, Position, Relative, Schema, SizeSource, item_width};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Renders `schema` and every struct nested in it as Markdown sections.
+///
+/// Each section has the type's doc comment, a field table with bit offsets,
+/// widths, byte order, size dependencies, layout options and field doc comments,
+/// and a bit diagram of the fields up to the first one whose position depends on
+/// the data. Offsets and widths include `align`, `sub_align` and `pad_*` padding
+/// exactly as the derive lays it out.
+pub fn markdown(schema: &Schema) -> String {
+    let mut schemas = Vec::new();
+    collect_schemas(schema, &mut schemas);
+    let sections: Vec<String> = schemas.into_iter().map(section).collect();
+    sections.join("\n")
+}
+
+/// `schema` and the nested structs it uses, each once, in first-use order.
+fn collect_schemas<'a>(schema: &'a Schema, out: &mut Vec<&'a Schema>) {
+    if out.iter().any(|seen| seen.name == schema.name) {
+        return;
+    }
+    out.push(schema);
+    for field in &schema.fields {
+        collect_layout_schemas(&field.layout, out);
+    }
+}
+
+fn collect_layout_schemas<'a>(layout: &'a Layout, out: &mut Vec<&'a Schema>) {
+    match layout {
+        Layout::Struct(schema) => collect_schemas(schema, out),
+        Layout::Array { item, .. } | Layout::Seq { item } => collect_layout_schemas(item, out),
+        Layout::Tuple(items) => {
+            for item in items {
+                collect_layout_schemas(item, out);
+            }
+        }
+        Layout::Flags { repr, .. } => collect_layout_schemas(repr, out),
+        _ => {}
+    }
+}
+
+fn section(schema: &Schema) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "## {}\n", schema.name);
+    if !schema.doc.is_empty() {
+        let _ = writeln!(out, "{}\n", schema.doc);
+    }
+    out.push_str("| Offset | Width | Field | Type | Byte order | Layout | Description |\n");
+    out.push_str("|---|---|---|---|---|---|---|\n");
+
+    let mut offset = Some(0);
+    let mut diagram = Vec::new();
+    let mut diagram_open = true;
+    for field in &schema.fields {
+        let width = field.bit_width();
+        if width.is_none() {
+            diagram_open = false;
+        }
+        let field_offset = offset.map(|offset| offset + field.pad_before);
+        if diagram_open && let (Some(start), Some(width)) = (offset, width) {
+            push_field_ranges(&mut diagram, field, start, width);
+        }
+        let _ = writeln!(
+            out,
+            "| {} | {} | `{}` | {} | {} | {} | {} |",
+            match (field_offset, field.position == Position::Sequential) {
+                (Some(offset), true) => offset.to_string(),
+                _ => "—".into(),
+            },
+            width_text(field),
+            field.name,
+            cell(&type_text(field)),
+            byte_order(&field.layout, field.bits, &schema.bit_order),
+            cell(&layout_notes(field).join("; ")),
+            cell(&field.doc.replace('\n', " ")),
+        );
+        offset = offset.zip(width).map(|(offset, width)| offset + width);
+    }
+
+    out.push('\n');
+    match offset {
+        Some(total) if total % 8 == 0 => {
+            let _ = writeln!(out, "Total: {} bits ({} bytes).", total, total / 8);
+        }
+        Some(total) => {
+            let _ = writeln!(out, "Total: {} bits.", total);
+        }
+        None => out.push_str("Total: depends on the data.\n"),
+    }
+    if !diagram.is_empty() {
+        let _ = write!(out, "\n```text\n{}```\n", bit_diagram(&diagram));
+        if offset.is_none() {
+            out.push_str("\nLater fields have data-dependent positions and are not drawn.\n");
+        }
+    }
+    out
+}
+
+/// Diagram cells for a fixed-width field starting at `start`, including its padding.
+fn push_field_ranges(ranges: &mut Vec<BitRange>, field: &FieldSchema, start: usize, width: usize) {
+    let mut push = |label: &str, start: usize, len_bits: usize| {
+        if len_bits > 0 {
+            ranges.push(BitRange {
+                label: label.into(),
+                start_bit: start,
+                len_bits,
+            });
+        }
+    };
+    let value_width = width - field.pad_before - field.pad_after;
+    let unaligned = field
+        .layout
+        .sized_bit_width(field.bits, &field.aligns, &field.sizes)
+        .unwrap_or(value_width);
+    push("(pad)", start, field.pad_before);
+    let value_start = start + field.pad_before;
+    push(&field.name, value_start, unaligned);
+    push("(align)", value_start + unaligned, value_width - unaligned);
+    push("(pad)", value_start + value_width, field.pad_after);
+}
+
+/// Escapes a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn type_text(field: &FieldSchema) -> String {
+    match &field.wire_type {
+        Some(wire) => format!("`{}` (wire `{}`)", field.rust_type, wire),
+        None => format!("`{}`", field.rust_type),
+    }
+}
+
+fn width_text(field: &FieldSchema) -> String {
+    if field.presence.skip {
+        return "0".into();
+    }
+    if let Some(width) = field.bit_width() {
+        return width.to_string();
+    }
+    let count = match (&field.size, field.sizes.first().filter(|&&size| size != 0)) {
+        (SizeSource::Field(name), _) => Some(format!("`{}`", name)),
+        (SizeSource::Func(name), _) => Some(format!("`{}()`", name)),
+        (SizeSource::None, Some(size)) => Some(size.to_string()),
+        (SizeSource::None, None) => None,
+    };
+    let item_width = match &field.layout {
+        Layout::Seq { item } => item_width(item, field.bits, &field.aligns, &field.sizes),
+        _ => None,
+    };
+    match (count, item_width) {
+        (Some(count), Some(width)) => format!("{} × {}", count, width),
+        (Some(count), None) => format!("{} × dynamic", count),
+        _ => "dynamic".into(),
+    }
+}
+
+/// Byte order of multi-byte numbers in the layout, or `—` if nothing spans bytes.
+fn byte_order(layout: &Layout, bits: Option<usize>, bit_order: &str) -> &'static str {
+    let multi_byte = match layout {
+        Layout::Int { bits: full, .. } | Layout::Fixed { bits: full, .. } => {
+            bits.unwrap_or(*full) > 8
+        }
+        Layout::Float { bits, .. } => *bits > 8,
+        Layout::Flags { repr, .. } => return byte_order(repr, bits, bit_order),
+        Layout::Array { item, .. } | Layout::Seq { item } => {
+            return byte_order(item, bits, bit_order);
+        }
+        Layout::Tuple(items) => items
+            .iter()
+            .any(|item| byte_order(item, bits, bit_order) != "—"),
+        Layout::Struct(schema) => return order_name(&schema.bit_order),
+        Layout::Bool | Layout::Reserved { .. } | Layout::Opaque => false,
+    };
+    if multi_byte {
+        order_name(bit_order)
+    } else {
+        "—"
+    }
+}
+
+fn order_name(bit_order: &str) -> &'static str {
+    match bit_order {
+        "Lsb0" => "little-endian",
+        "Msb0" => "big-endian",
+        _ => "custom",
+    }
+}
+
+/// The field's layout options in words.
+fn layout_notes(field: &FieldSchema) -> Vec<String> {
+    let mut notes = Vec::new();
+    let presence = &field.presence;
+    if presence.skip {
+        notes.push("not on the wire".into());
+    }
+    if let Some(cond) = &presence.cond {
+        notes.push(format!("only if `{}`", cond));
+    }
+    match (&presence.since, &presence.until) {
+        (Some(since), Some(until)) => {
+            notes.push(format!("versions {} to {} (exclusive)", since, until))
+        }
+        (Some(since), None) => notes.push(format!("since version {}", since)),
+        (None, Some(until)) => notes.push(format!("before version {}", until)),
+        (None, None) => {}
+    }
+    match &field.position {
+        Position::Sequential => {}
+        Position::OffsetField { field, relative } => notes.push(format!(
+            "at the byte offset in `{}` from {}",
+            field,
+            relative_text(*relative)
+        )),
+        Position::Seek { expr, relative } => notes.push(format!(
+            "at byte offset `{}` from {}",
+            expr,
+            relative_text(*relative)
+        )),
+    }
+    if let Some(bits) = field.bits {
+        notes.push(format!("{} bits wide", bits));
+    }
+    if field.pad_before > 0 {
+        notes.push(format!("{} padding bits before", field.pad_before));
+    }
+    if let Some(align) = field.align {
+        notes.push(format!("padded to a multiple of {} bits", align));
+    }
+    for (level, &align) in field.aligns.iter().enumerate() {
+        if align != 0 {
+            notes.push(format!(
+                "{} padded to a multiple of {} bits",
+                element_name(level),
+                align
+            ));
+        }
+    }
+    if field.pad_after > 0 {
+        notes.push(format!("{} padding bits after", field.pad_after));
+    }
+    match &field.size {
+        SizeSource::Field(name) => notes.push(format!("count from `{}`", name)),
+        SizeSource::Func(name) => notes.push(format!("count from `{}()`", name)),
+        SizeSource::None => {}
+    }
+    for (level, &size) in field.sizes.iter().enumerate() {
+        if size != 0 && (level > 0 || field.size == SizeSource::None) {
+            notes.push(format!("{} count {}", element_name(level), size));
+        }
+    }
+    if let Some(calc) = &field.calc {
+        notes.push(format!("computed as `{}`", calc));
+    }
+    if field.layout == Layout::Opaque && !presence.skip {
+        notes.push("custom encoding".into());
+    }
+    notes
+}
+
+fn relative_text(relative: Relative) -> &'static str {
+    match relative {
+        Relative::Start => "the struct start",
+        Relative::Current => "its sequential position",
+    }
+}
+
+/// What a per-level option applies to: the elements of the field at level 0,
+/// their elements at level 1, and so on.
+fn element_name(level: usize) -> String {
+    match level {
+        0 => "elements".into(),
+        level => format!("level-{} elements", level + 1),
+    }
+}
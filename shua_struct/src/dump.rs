@@ -0,0 +1,226 @@
+//! Human-readable renderings of parsed input, built on [`Span`]s.
+//!
+//! [`hex_dump`] labels every byte of a buffer with the fields it belongs to, and
+//! [`bit_diagram`] draws fields as an RFC-style box diagram, which is the
+//! clearer picture for sub-byte fields.
+
+use crate::Span;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use bitvec::prelude::*;
+use core::fmt::Write;
+
+const BYTES_PER_LINE: usize = 16;
+const BITS_PER_ROW: usize = 32;
+const COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+/// A labeled range of bits, as drawn by [`bit_diagram`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitRange {
+    pub label: String,
+    pub start_bit: usize,
+    pub len_bits: usize,
+}
+
+/// The leaf values of `span` (those without children of their own), in input order.
+///
+/// Each is labeled with its path, plus its value if it has one.
+pub fn leaves<O: BitOrder>(span: &Span<O>) -> Vec<BitRange> {
+    let mut leaves: Vec<BitRange> = span
+        .iter()
+        .filter(|span| span.children.is_empty() && span.len_bits > 0)
+        .map(|span| BitRange {
+            label: if span.value.is_empty() {
+                span.path.clone()
+            } else {
+                format!("{} = {}", span.path, span.value)
+            },
+            start_bit: span.start_bit,
+            len_bits: span.len_bits,
+        })
+        .collect();
+    leaves.sort_by_key(|leaf| leaf.start_bit);
+    leaves
+}
+
+/// Renders `bytes` as a hex dump with one line per group of bytes holding the same fields.
+///
+/// `span` is the trace of a value parsed from the start of `bytes`; bytes no field
+/// covers are labeled as padding. With `color`, each group is highlighted with ANSI
+/// escape codes so neighboring fields stand apart.
+pub fn hex_dump<O: BitOrder>(bytes: &[u8], span: &Span<O>, color: bool) -> String {
+    // Groups of byte ranges with the labels of the fields overlapping them.
+    let mut groups: Vec<(usize, usize, Vec<String>)> = Vec::new();
+    for leaf in leaves(span) {
+        let first = leaf.start_bit / 8;
+        let end = (leaf.start_bit + leaf.len_bits).div_ceil(8);
+        match groups.last_mut() {
+            Some((_, group_end, labels)) if first < *group_end => {
+                *group_end = (*group_end).max(end);
+                labels.push(leaf.label);
+            }
+            _ => groups.push((first, end, Vec::from([leaf.label]))),
+        }
+    }
+
+    let mut out = String::new();
+    let mut pos = 0;
+    let mut color_index = 0;
+    let line = |out: &mut String, start: usize, end: usize, label: &str, color: Option<u8>| {
+        for (i, chunk_start) in (start..end).step_by(BYTES_PER_LINE).enumerate() {
+            let chunk = &bytes[chunk_start..end.min(chunk_start + BYTES_PER_LINE)];
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let hex = hex.join(" ");
+            let label = if i == 0 { label } else { "" };
+            let _ = match color {
+                Some(code) => writeln!(
+                    out,
+                    "{:08x}  \x1b[{}m{:<width$}  {}\x1b[0m",
+                    chunk_start,
+                    code,
+                    hex,
+                    label,
+                    width = BYTES_PER_LINE * 3 - 1
+                ),
+                None => writeln!(
+                    out,
+                    "{:08x}  {:<width$}  {}",
+                    chunk_start,
+                    hex,
+                    label,
+                    width = BYTES_PER_LINE * 3 - 1
+                ),
+            };
+        }
+    };
+    for (start, end, labels) in groups {
+        let (start, end) = (start.min(bytes.len()), end.min(bytes.len()));
+        if pos < start {
+            line(&mut out, pos, start, "(padding)", None);
+        }
+        let code = color.then(|| COLORS[color_index % COLORS.len()]);
+        color_index += 1;
+        line(&mut out, start.max(pos), end, &labels.join(", "), code);
+        pos = pos.max(end);
+    }
+    if pos < bytes.len() {
+        line(&mut out, pos, bytes.len(), "(unparsed)", None);
+    }
+    out
+}
+
+/// Draws the leaf fields of `span` as an RFC-style bit diagram; see [`bit_diagram`].
+///
+/// Fields are labeled with their last path segment.
+pub fn span_diagram<O: BitOrder>(span: &Span<O>) -> String {
+    let ranges: Vec<BitRange> = span
+        .iter()
+        .filter(|span| span.children.is_empty() && span.len_bits > 0)
+        .map(|leaf| BitRange {
+            label: last_segment(&leaf.path).into(),
+            start_bit: leaf.start_bit - span.start_bit,
+            len_bits: leaf.len_bits,
+        })
+        .collect();
+    bit_diagram(&ranges)
+}
+
+/// The field name a span is best known by: `level`, `skills[2]` or `pos.0`.
+fn last_segment(path: &str) -> &str {
+    let name_start = |end: usize| path[..end].rfind('.').map_or(0, |dot| dot + 1);
+    let start = name_start(path.len());
+    let name = &path[start..];
+    // Elements and tuple members keep their parent's name.
+    if !name.starts_with(|c: char| c.is_ascii_digit()) || start == 0 {
+        return name;
+    }
+    &path[name_start(start - 1)..]
+}
+
+/// Draws bit ranges as an RFC-style box diagram, 32 bits per row.
+///
+/// Columns are bit offsets in the input's bit order. Bits no range covers are
+/// drawn as unlabeled cells; ranges crossing a row boundary continue on the next
+/// row. Labels too long for their cell are cut short.
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     count     | level |   n   |            length             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+pub fn bit_diagram(ranges: &[BitRange]) -> String {
+    let mut ranges: Vec<&BitRange> = ranges.iter().filter(|range| range.len_bits > 0).collect();
+    ranges.sort_by_key(|range| range.start_bit);
+    let total = ranges
+        .iter()
+        .map(|range| range.start_bit + range.len_bits)
+        .max()
+        .unwrap_or(0);
+
+    // Contiguous cells covering every bit, with gaps as unlabeled cells.
+    let mut cells: Vec<(&str, usize, usize)> = Vec::new();
+    let mut pos = 0;
+    for range in ranges {
+        let start = range.start_bit.max(pos);
+        let end = range.start_bit + range.len_bits;
+        if end <= pos {
+            continue;
+        }
+        if pos < start {
+            cells.push(("", pos, start));
+        }
+        cells.push((&range.label, start, end));
+        pos = end;
+    }
+
+    let mut out = String::new();
+    let width = total.min(BITS_PER_ROW);
+    if width == 0 {
+        return out;
+    }
+    out.push(' ');
+    for bit in 0..width {
+        out.push(if bit % 10 == 0 {
+            char::from_digit((bit / 10 % 10) as u32, 10).unwrap_or(' ')
+        } else {
+            ' '
+        });
+        out.push(' ');
+    }
+    out.truncate(out.trim_end().len());
+    out.push_str("\n ");
+    for bit in 0..width {
+        out.push(char::from_digit((bit % 10) as u32, 10).unwrap_or(' '));
+        out.push(' ');
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    push_border(&mut out, width);
+
+    for row_start in (0..total).step_by(BITS_PER_ROW) {
+        let row_end = (row_start + BITS_PER_ROW).min(total);
+        out.push('|');
+        for &(label, start, end) in &cells {
+            let (start, end) = (start.max(row_start), end.min(row_end));
+            if start >= end {
+                continue;
+            }
+            let cell_width = (end - start) * 2 - 1;
+            let label: String = label.chars().take(cell_width).collect();
+            let _ = write!(out, "{:^cell_width$}|", label);
+        }
+        out.push('\n');
+        push_border(&mut out, row_end - row_start);
+    }
+    out
+}
+
+fn push_border(out: &mut String, bits: usize) {
+    for _ in 0..bits {
+        out.push_str("+-");
+    }
+    out.push_str("+\n");
+}
@@ -0,0 +1,382 @@
+//! The expression subset runtime schemas evaluate for `cond`, `since`/`until`, `seek` and `calc`.
+//!
+//! Expressions are Rust as the derive writes them into the schema: integer and
+//! bool literals, fields as `self.name` (or just `name`), nested fields and tuple
+//! members, `.len()`, `as` casts to integer types, and the unary, arithmetic,
+//! bitwise, comparison and logical operators with Rust's precedence.
+
+use super::DynamicValue;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const INT_SUFFIXES: &[&str] = &[
+    "u128", "i128", "usize", "isize", "u64", "i64", "u32", "i32", "u16", "i16", "u8", "i8",
+];
+
+const PUNCTS: &[&str] = &[
+    "==", "!=", "<=", ">=", "<<", ">>", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^", "!",
+    "<", ">", "(", ")", ".",
+];
+
+/// Binary operators from loosest to tightest binding.
+const BINARY: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["==", "!=", "<", "<=", ">", ">="],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+/// Evaluates `src` against the fields of the struct being parsed or built.
+pub(crate) fn eval(src: &str, fields: &[(String, DynamicValue)]) -> Result<DynamicValue, String> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.binary(0)?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("unexpected `{}`", token));
+    }
+    match expr.eval(fields)? {
+        Val::Int(value) => Ok(DynamicValue::Int(value)),
+        Val::Bool(value) => Ok(DynamicValue::Bool(value)),
+        Val::Ref(value) => Ok(value.clone()),
+        Val::SelfValue => Err("`self` is not a value".into()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i128),
+    Punct(&'static str),
+}
+
+impl core::fmt::Display for Token {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Token::Ident(name) => f.write_str(name),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Punct(punct) => f.write_str(punct),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = src.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let word_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if c.is_ascii_digit() {
+            tokens.push(Token::Int(int_literal(&rest[..word_len])?));
+            rest = &rest[word_len..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            tokens.push(Token::Ident(rest[..word_len].to_string()));
+            rest = &rest[word_len..];
+        } else if let Some(punct) = PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(format!("unexpected `{}`", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn int_literal(word: &str) -> Result<i128, String> {
+    let (digits, radix) = match word.get(..2) {
+        Some("0x") => (&word[2..], 16),
+        Some("0o") => (&word[2..], 8),
+        Some("0b") => (&word[2..], 2),
+        _ => (word, 10),
+    };
+    let digits = INT_SUFFIXES
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .unwrap_or(digits);
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    i128::from_str_radix(&digits, radix).map_err(|_| format!("invalid integer `{}`", word))
+}
+
+enum Expr {
+    Int(i128),
+    Bool(bool),
+    SelfValue,
+    /// A field of the struct, written without `self.`.
+    Name(String),
+    Field(Box<Expr>, String),
+    Member(Box<Expr>, usize),
+    Len(Box<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Cast(Box<Expr>, String),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        let Some(ops) = BINARY.get(level) else {
+            return self.cast();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(Token::Punct(op)) = self.tokens.get(self.pos)
+            && ops.contains(op)
+        {
+            let op = *op;
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn cast(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.tokens.get(self.pos) == Some(&Token::Ident("as".into())) {
+            self.pos += 1;
+            match self.next() {
+                Some(Token::Ident(ty)) => expr = Expr::Cast(Box::new(expr), ty),
+                _ => return Err("expected a type after `as`".into()),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        for op in ["!", "-"] {
+            if self.eat(op) {
+                return Ok(Expr::Unary(op, Box::new(self.unary()?)));
+            }
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.eat(".") {
+            expr = match self.next() {
+                Some(Token::Ident(name)) if self.eat("(") => {
+                    if !self.eat(")") || name != "len" {
+                        return Err(format!("unsupported method `{}`", name));
+                    }
+                    Expr::Len(Box::new(expr))
+                }
+                Some(Token::Ident(name)) => Expr::Field(Box::new(expr), name),
+                Some(Token::Int(index)) => Expr::Member(Box::new(expr), index as usize),
+                _ => return Err("expected a field after `.`".into()),
+            };
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Int(value)) => Ok(Expr::Int(value)),
+            Some(Token::Ident(name)) => Ok(match name.as_str() {
+                "true" => Expr::Bool(true),
+                "false" => Expr::Bool(false),
+                "self" => Expr::SelfValue,
+                _ => Expr::Name(name),
+            }),
+            Some(Token::Punct("(")) => {
+                let expr = self.binary(0)?;
+                if !self.eat(")") {
+                    return Err("expected `)`".into());
+                }
+                Ok(expr)
+            }
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
+
+enum Val<'a> {
+    SelfValue,
+    Ref(&'a DynamicValue),
+    Int(i128),
+    Bool(bool),
+}
+
+impl Val<'_> {
+    fn int(&self) -> Result<i128, String> {
+        match self {
+            Val::Int(value) | Val::Ref(DynamicValue::Int(value)) => Ok(*value),
+            Val::Ref(value) => Err(format!("expected an integer, got {}", value.kind())),
+            _ => Err("expected an integer".into()),
+        }
+    }
+
+    fn bool(&self) -> Result<bool, String> {
+        match self {
+            Val::Bool(value) | Val::Ref(DynamicValue::Bool(value)) => Ok(*value),
+            Val::Ref(value) => Err(format!("expected a bool, got {}", value.kind())),
+            _ => Err("expected a bool".into()),
+        }
+    }
+
+    fn is_bool(&self) -> bool {
+        matches!(self, Val::Bool(_) | Val::Ref(DynamicValue::Bool(_)))
+    }
+}
+
+impl Expr {
+    fn eval<'a>(&self, fields: &'a [(String, DynamicValue)]) -> Result<Val<'a>, String> {
+        Ok(match self {
+            Expr::Int(value) => Val::Int(*value),
+            Expr::Bool(value) => Val::Bool(*value),
+            Expr::SelfValue => Val::SelfValue,
+            Expr::Name(name) => Val::Ref(lookup(fields, name)?),
+            Expr::Field(base, name) => match base.eval(fields)? {
+                Val::SelfValue => Val::Ref(lookup(fields, name)?),
+                Val::Ref(value @ DynamicValue::Struct(_)) => Val::Ref(
+                    value
+                        .get(name)
+                        .ok_or_else(|| format!("no field `{}`", name))?,
+                ),
+                _ => return Err(format!("no field `{}`", name)),
+            },
+            Expr::Member(base, index) => match base.eval(fields)? {
+                Val::Ref(DynamicValue::List(items)) => Val::Ref(
+                    items
+                        .get(*index)
+                        .ok_or_else(|| format!("no member {}", index))?,
+                ),
+                _ => return Err(format!("no member {}", index)),
+            },
+            Expr::Len(base) => match base.eval(fields)? {
+                Val::Ref(DynamicValue::List(items)) => Val::Int(items.len() as i128),
+                _ => return Err("`len()` needs a list".into()),
+            },
+            Expr::Unary(op, operand) => {
+                let operand = operand.eval(fields)?;
+                match *op {
+                    "!" if operand.is_bool() => Val::Bool(!operand.bool()?),
+                    "!" => Val::Int(!operand.int()?),
+                    _ => Val::Int(operand.int()?.checked_neg().ok_or("overflow")?),
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => binary(op, lhs, rhs, fields)?,
+            Expr::Cast(operand, ty) => {
+                let operand = operand.eval(fields)?;
+                let value = if operand.is_bool() {
+                    operand.bool()? as i128
+                } else {
+                    operand.int()?
+                };
+                Val::Int(cast(value, ty)?)
+            }
+        })
+    }
+}
+
+fn lookup<'a>(
+    fields: &'a [(String, DynamicValue)],
+    name: &str,
+) -> Result<&'a DynamicValue, String> {
+    fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| format!("`{}` is not on the wire", name))
+}
+
+fn binary<'a>(
+    op: &str,
+    lhs: &Expr,
+    rhs: &Expr,
+    fields: &'a [(String, DynamicValue)],
+) -> Result<Val<'a>, String> {
+    let lhs = lhs.eval(fields)?;
+    // `&&` and `||` short-circuit, so `self.has_len && self.len > 0` works when `len` is absent.
+    match op {
+        "&&" => return Ok(Val::Bool(lhs.bool()? && rhs.eval(fields)?.bool()?)),
+        "||" => return Ok(Val::Bool(lhs.bool()? || rhs.eval(fields)?.bool()?)),
+        _ => {}
+    }
+    let rhs = rhs.eval(fields)?;
+    if lhs.is_bool() || rhs.is_bool() {
+        let (a, b) = (lhs.bool()?, rhs.bool()?);
+        return Ok(Val::Bool(match op {
+            "==" => a == b,
+            "!=" => a != b,
+            "&" => a & b,
+            "|" => a | b,
+            "^" => a ^ b,
+            _ => return Err(format!("`{}` needs integers", op)),
+        }));
+    }
+    let (a, b) = (lhs.int()?, rhs.int()?);
+    let shift = || u32::try_from(b).map_err(|_| String::from("shift out of range"));
+    Ok(match op {
+        "==" => Val::Bool(a == b),
+        "!=" => Val::Bool(a != b),
+        "<" => Val::Bool(a < b),
+        "<=" => Val::Bool(a <= b),
+        ">" => Val::Bool(a > b),
+        ">=" => Val::Bool(a >= b),
+        _ => Val::Int(
+            match op {
+                "+" => a.checked_add(b),
+                "-" => a.checked_sub(b),
+                "*" => a.checked_mul(b),
+                "/" => a.checked_div(b),
+                "%" => a.checked_rem(b),
+                "&" => Some(a & b),
+                "|" => Some(a | b),
+                "^" => Some(a ^ b),
+                "<<" => a.checked_shl(shift()?),
+                _ => a.checked_shr(shift()?),
+            }
+            .ok_or_else(|| format!("`{} {} {}` overflows", a, op, b))?,
+        ),
+    })
+}
+
+/// Truncates or sign-extends `value` like an `as` cast to the integer type `ty`.
+fn cast(value: i128, ty: &str) -> Result<i128, String> {
+    let (signed, bits) = match ty {
+        "u8" => (false, 8),
+        "u16" => (false, 16),
+        "u32" => (false, 32),
+        "u64" | "usize" => (false, 64),
+        "i8" => (true, 8),
+        "i16" => (true, 16),
+        "i32" => (true, 32),
+        "i64" | "isize" => (true, 64),
+        "u128" | "i128" => return Ok(value),
+        _ => return Err(format!("cannot cast to `{}`", ty)),
+    };
+    let shift = 128 - bits;
+    Ok(if signed {
+        (value << shift) >> shift
+    } else {
+        ((value as u128) << shift >> shift) as i128
+    })
+}
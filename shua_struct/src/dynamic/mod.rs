@@ -0,0 +1,724 @@
+//! Parsing and building with a [`Schema`] that is only known at runtime.
+//!
+//! A schema from [`BinaryStruct`](crate::BinaryStruct)'s `schema()`, built by hand,
+//! or (with the `serde` feature) deserialized from JSON or TOML is interpreted
+//! field by field into a [`DynamicValue`] tree. Leaf values go through the same
+//! [`BinaryField`] impls as derived types, and containers use the same element
+//! alignment rules, so a runtime schema reads and writes exactly the bits of the
+//! type it describes.
+//!
+//! ```ignore
+//! let schema: Schema = serde_json::from_str(r#"{
+//!     "name": "Record",
+//!     "bit_order": "Lsb0",
+//!     "fields": [
+//!         { "name": "kind", "layout": { "Int": { "signed": false, "bits": 8 } }, "bits": 4 },
+//!         { "name": "urgent", "layout": "Bool", "pad_after": 3 },
+//!         { "name": "len", "layout": { "Int": { "signed": false, "bits": 16 } } },
+//!         { "name": "data", "layout": { "Seq": { "item": { "Int": { "signed": false, "bits": 8 } } } },
+//!           "size": { "Field": "len" } }
+//!     ]
+//! }"#)?;
+//! let record = shua_struct::dynamic::from_bytes(&schema, &bytes)?;
+//! assert_eq!(record.get("len"), Some(&DynamicValue::Int(2)));
+//! ```
+//!
+//! `cond`, `since`/`until`, `seek` and `calc` expressions are evaluated by a small
+//! interpreter covering literals, `self.field` paths, `.len()`, integer `as` casts
+//! and Rust's operators. What needs compiled code cannot be interpreted:
+//! `size_func` counts and `parse_with` fields ([`Layout::Opaque`]); 16-bit floats
+//! need the `half` feature. Fields that are not on the wire are left out of
+//! the parsed struct instead of taking their `default`, and nested structs with
+//! an empty `bit_order` use their parent's.
+
+mod expr;
+mod value;
+
+pub use value::DynamicValue;
+
+use crate::field::array::{build_seq, parse_seq, seq_size};
+use crate::schema::{FieldSchema, FloatKind, Position, Relative, SizeSource};
+use crate::{BinaryField, Error, Layout, Options, Schema};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use bitvec::prelude::*;
+
+/// Parses `bits` as the struct described by `schema`, returning the value and the bits consumed.
+pub fn parse<O: DynamicOrder>(
+    schema: &Schema,
+    bits: &BitSlice<u8, O>,
+) -> Result<(DynamicValue, usize), Error> {
+    let (fields, l) = parse_struct(schema, bits)?;
+    Ok((DynamicValue::Struct(fields), l))
+}
+
+/// Builds `value`, a [`DynamicValue::Struct`], as the struct described by `schema`.
+pub fn build<O: DynamicOrder>(
+    schema: &Schema,
+    value: &DynamicValue,
+) -> Result<BitVec<u8, O>, Error> {
+    build_struct(schema, value)
+}
+
+/// Parses `bytes` with the bit order named in `schema`.
+pub fn from_bytes(schema: &Schema, bytes: &[u8]) -> Result<DynamicValue, Error> {
+    match schema.bit_order.as_str() {
+        "Lsb0" => parse(schema, bytes.view_bits::<Lsb0>()).map(|(value, _)| value),
+        "Msb0" => parse(schema, bytes.view_bits::<Msb0>()).map(|(value, _)| value),
+        other => Err(unknown_order(other)),
+    }
+}
+
+/// Builds `value` with the bit order named in `schema`; trailing bits of the last byte are zero.
+pub fn to_bytes(schema: &Schema, value: &DynamicValue) -> Result<Vec<u8>, Error> {
+    match schema.bit_order.as_str() {
+        "Lsb0" => build::<Lsb0>(schema, value).map(BitVec::into_vec),
+        "Msb0" => build::<Msb0>(schema, value).map(BitVec::into_vec),
+        other => Err(unknown_order(other)),
+    }
+}
+
+fn unknown_order(order: &str) -> Error {
+    format!("unknown bit order `{}`; expected `Lsb0` or `Msb0`", order).into()
+}
+
+/// A bit order the primitive types implement [`BinaryField`] for: [`Lsb0`] or [`Msb0`].
+pub trait DynamicOrder: BitOrder + Sized {
+    /// Name of the order as written in [`Schema::bit_order`].
+    const NAME: &'static str;
+
+    /// Parses a `Bool`, `Int`, `Fixed`, `Flags` or `Float` layout as the primitive type it stands for.
+    fn parse_leaf(
+        layout: &Layout,
+        bits: &BitSlice<u8, Self>,
+        opts: &Option<Options>,
+    ) -> Result<(DynamicValue, usize), Error>;
+
+    /// Builds a value of a layout accepted by [`DynamicOrder::parse_leaf`].
+    fn build_leaf(
+        layout: &Layout,
+        value: &DynamicValue,
+        opts: &Option<Options>,
+    ) -> Result<BitVec<u8, Self>, Error>;
+}
+
+/// The primitive type a leaf layout is parsed as.
+#[derive(Clone, Copy)]
+enum Leaf {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    #[cfg(feature = "half")]
+    F16,
+    #[cfg(feature = "half")]
+    BF16,
+    F32,
+    F64,
+}
+
+fn leaf(layout: &Layout) -> Result<Leaf, Error> {
+    Ok(match layout {
+        Layout::Bool => Leaf::Bool,
+        Layout::Int { signed, bits } | Layout::Fixed { signed, bits, .. } => match (signed, bits) {
+            (false, 8) => Leaf::U8,
+            (false, 16) => Leaf::U16,
+            (false, 32) => Leaf::U32,
+            (false, 64) => Leaf::U64,
+            (true, 8) => Leaf::I8,
+            (true, 16) => Leaf::I16,
+            (true, 32) => Leaf::I32,
+            (true, 64) => Leaf::I64,
+            _ => return Err(format!("unsupported {}-bit integer", bits).into()),
+        },
+        #[cfg(feature = "half")]
+        Layout::Float {
+            bits: 16,
+            kind: FloatKind::Ieee,
+        } => Leaf::F16,
+        #[cfg(feature = "half")]
+        Layout::Float {
+            bits: 16,
+            kind: FloatKind::Bfloat,
+        } => Leaf::BF16,
+        #[cfg(not(feature = "half"))]
+        Layout::Float { bits: 16, .. } => {
+            return Err("16-bit floats need the `half` feature".into());
+        }
+        Layout::Float {
+            bits: 32,
+            kind: FloatKind::Ieee,
+        } => Leaf::F32,
+        Layout::Float {
+            bits: 64,
+            kind: FloatKind::Ieee,
+        } => Leaf::F64,
+        Layout::Float { bits, kind } => {
+            return Err(format!("unsupported {}-bit {:?} float", bits, kind).into());
+        }
+        Layout::Flags { repr, .. } => leaf(repr)?,
+        _ => return Err("not a primitive layout".into()),
+    })
+}
+
+fn parse_as<T: BinaryField<O>, O: BitOrder>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    wrap: impl FnOnce(T) -> DynamicValue,
+) -> Result<(DynamicValue, usize), Error> {
+    let (value, l) = T::parse(bits, opts)?;
+    Ok((wrap(value), l))
+}
+
+fn int<T: Into<i128>>(value: T) -> DynamicValue {
+    DynamicValue::Int(value.into())
+}
+
+fn build_bool<O: BitOrder>(
+    value: &DynamicValue,
+    opts: &Option<Options>,
+) -> Result<BitVec<u8, O>, Error> {
+    let value = value
+        .as_bool()
+        .ok_or_else(|| format!("bool build error: expected a bool, got {}", value.kind()))?;
+    value.build(opts)
+}
+
+fn build_int<T: BinaryField<O> + TryFrom<i128>, O: BitOrder>(
+    value: &DynamicValue,
+    opts: &Option<Options>,
+) -> Result<BitVec<u8, O>, Error> {
+    let name = core::any::type_name::<T>();
+    let value = value.as_int().ok_or_else(|| {
+        format!(
+            "{} build error: expected an integer, got {}",
+            name,
+            value.kind()
+        )
+    })?;
+    T::try_from(value)
+        .map_err(|_| format!("{} build error: {} does not fit in {}", name, value, name))?
+        .build(opts)
+}
+
+fn build_float<T: BinaryField<O>, O: BitOrder>(
+    value: &DynamicValue,
+    opts: &Option<Options>,
+    convert: impl FnOnce(f64) -> T,
+) -> Result<BitVec<u8, O>, Error> {
+    let value = value.as_float().ok_or_else(|| {
+        format!(
+            "{} build error: expected a float, got {}",
+            core::any::type_name::<T>(),
+            value.kind()
+        )
+    })?;
+    convert(value).build(opts)
+}
+
+macro_rules! impl_dynamic_order {
+    ($order:ty, $name:literal) => {
+        impl DynamicOrder for $order {
+            const NAME: &'static str = $name;
+
+            fn parse_leaf(
+                layout: &Layout,
+                bits: &BitSlice<u8, Self>,
+                opts: &Option<Options>,
+            ) -> Result<(DynamicValue, usize), Error> {
+                match leaf(layout)? {
+                    Leaf::Bool => parse_as::<bool, Self>(bits, opts, DynamicValue::Bool),
+                    Leaf::U8 => parse_as::<u8, Self>(bits, opts, int),
+                    Leaf::U16 => parse_as::<u16, Self>(bits, opts, int),
+                    Leaf::U32 => parse_as::<u32, Self>(bits, opts, int),
+                    Leaf::U64 => parse_as::<u64, Self>(bits, opts, int),
+                    Leaf::I8 => parse_as::<i8, Self>(bits, opts, int),
+                    Leaf::I16 => parse_as::<i16, Self>(bits, opts, int),
+                    Leaf::I32 => parse_as::<i32, Self>(bits, opts, int),
+                    Leaf::I64 => parse_as::<i64, Self>(bits, opts, int),
+                    #[cfg(feature = "half")]
+                    Leaf::F16 => parse_as::<half::f16, Self>(bits, opts, |value| {
+                        DynamicValue::Float(value.into())
+                    }),
+                    #[cfg(feature = "half")]
+                    Leaf::BF16 => parse_as::<half::bf16, Self>(bits, opts, |value| {
+                        DynamicValue::Float(value.into())
+                    }),
+                    Leaf::F32 => {
+                        parse_as::<f32, Self>(bits, opts, |value| DynamicValue::Float(value.into()))
+                    }
+                    Leaf::F64 => parse_as::<f64, Self>(bits, opts, DynamicValue::Float),
+                }
+            }
+
+            fn build_leaf(
+                layout: &Layout,
+                value: &DynamicValue,
+                opts: &Option<Options>,
+            ) -> Result<BitVec<u8, Self>, Error> {
+                match leaf(layout)? {
+                    Leaf::Bool => build_bool(value, opts),
+                    Leaf::U8 => build_int::<u8, Self>(value, opts),
+                    Leaf::U16 => build_int::<u16, Self>(value, opts),
+                    Leaf::U32 => build_int::<u32, Self>(value, opts),
+                    Leaf::U64 => build_int::<u64, Self>(value, opts),
+                    Leaf::I8 => build_int::<i8, Self>(value, opts),
+                    Leaf::I16 => build_int::<i16, Self>(value, opts),
+                    Leaf::I32 => build_int::<i32, Self>(value, opts),
+                    Leaf::I64 => build_int::<i64, Self>(value, opts),
+                    #[cfg(feature = "half")]
+                    Leaf::F16 => build_float(value, opts, half::f16::from_f64),
+                    #[cfg(feature = "half")]
+                    Leaf::BF16 => build_float(value, opts, half::bf16::from_f64),
+                    Leaf::F32 => build_float(value, opts, |value| value as f32),
+                    Leaf::F64 => build_float(value, opts, |value| value),
+                }
+            }
+        }
+    };
+}
+
+impl_dynamic_order!(Lsb0, "Lsb0");
+impl_dynamic_order!(Msb0, "Msb0");
+
+fn parse_layout<O: DynamicOrder>(
+    layout: &Layout,
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+) -> Result<(DynamicValue, usize), Error> {
+    match layout {
+        Layout::Reserved {
+            bits: width,
+            strict,
+        } => {
+            // Same checks as `Reserved<BITS, STRICT>`.
+            if bits.len() < *width {
+                return Err(format!(
+                    "Reserved parse error: not enough bits (needed {}, got {})",
+                    width,
+                    bits.len()
+                )
+                .into());
+            }
+            if *strict && bits[..*width].any() {
+                return Err(format!(
+                    "Reserved parse error: non-zero bits in {} reserved bits",
+                    width
+                )
+                .into());
+            }
+            Ok((DynamicValue::Reserved, *width))
+        }
+        Layout::Array { len, item } => parse_list(item, bits, opts, *len),
+        Layout::Seq { item } => parse_list(item, bits, opts, seq_size(opts, "Vec")?),
+        Layout::Tuple(items) => {
+            let mut values = Vec::new();
+            let mut offset = 0;
+            for (i, item) in items.iter().enumerate() {
+                let index = i.to_string();
+                let rest = bits.get(offset..).ok_or_else(|| {
+                    Error::new("tuple parse error: not enough bits").in_field(&index, offset)
+                })?;
+                let (value, l) =
+                    parse_layout(item, rest, opts).map_err(|e| e.in_field(&index, offset))?;
+                values.push(value);
+                offset += l;
+            }
+            Ok((DynamicValue::List(values), offset))
+        }
+        Layout::Struct(schema) => {
+            let (fields, l) = parse_struct(schema, bits)?;
+            Ok((DynamicValue::Struct(fields), l))
+        }
+        Layout::Opaque => Err(opaque()),
+        _ => O::parse_leaf(layout, bits, opts),
+    }
+}
+
+fn parse_list<O: DynamicOrder>(
+    item: &Layout,
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    size: usize,
+) -> Result<(DynamicValue, usize), Error> {
+    let mut items = Vec::new();
+    let l = parse_seq(
+        bits,
+        opts,
+        size,
+        None,
+        |bits, opts, _| parse_layout(item, bits, opts),
+        |value| {
+            items.push(value);
+            Ok(())
+        },
+    )?;
+    Ok((DynamicValue::List(items), l))
+}
+
+fn opaque() -> Error {
+    "opaque layout: `parse_with` fields and types without a layout cannot be interpreted".into()
+}
+
+fn build_layout<O: DynamicOrder>(
+    layout: &Layout,
+    value: &DynamicValue,
+    opts: &Option<Options>,
+) -> Result<BitVec<u8, O>, Error> {
+    match layout {
+        Layout::Reserved { bits, .. } => Ok(BitVec::repeat(false, *bits)),
+        Layout::Array { len, item } => {
+            let items = list(value, "array")?;
+            if items.len() != *len {
+                return Err(format!(
+                    "array build error: expected {} elements, got {}",
+                    len,
+                    items.len()
+                )
+                .into());
+            }
+            build_seq(items, opts, |value, opts| build_layout(item, value, opts))
+        }
+        Layout::Seq { item } => build_seq(list(value, "Vec")?, opts, |value, opts| {
+            build_layout(item, value, opts)
+        }),
+        Layout::Tuple(items) => {
+            let values = list(value, "tuple")?;
+            if values.len() != items.len() {
+                return Err(format!(
+                    "tuple build error: expected {} members, got {}",
+                    items.len(),
+                    values.len()
+                )
+                .into());
+            }
+            let mut bv = BitVec::new();
+            for (i, (item, value)) in items.iter().zip(values).enumerate() {
+                let start = bv.len();
+                bv.extend(
+                    build_layout::<O>(item, value, opts)
+                        .map_err(|e| e.in_field(&i.to_string(), start))?,
+                );
+            }
+            Ok(bv)
+        }
+        Layout::Struct(schema) => build_struct(schema, value),
+        Layout::Opaque => Err(opaque()),
+        _ => O::build_leaf(layout, value, opts),
+    }
+}
+
+fn list<'a>(value: &'a DynamicValue, name: &str) -> Result<&'a [DynamicValue], Error> {
+    value.as_list().ok_or_else(|| {
+        format!(
+            "{} build error: expected a list, got {}",
+            name,
+            value.kind()
+        )
+        .into()
+    })
+}
+
+fn check_order<O: DynamicOrder>(schema: &Schema, action: &str) -> Result<(), Error> {
+    if schema.bit_order.is_empty() || schema.bit_order == O::NAME {
+        return Ok(());
+    }
+    Err(format!(
+        "{} {} error: bit order {} does not match {}",
+        schema.name,
+        action,
+        schema.bit_order,
+        O::NAME
+    )
+    .into())
+}
+
+/// Evaluates `src` against the struct's fields so far.
+fn eval(src: &str, fields: &[(String, DynamicValue)]) -> Result<DynamicValue, Error> {
+    expr::eval(src, fields).map_err(|msg| format!("cannot evaluate `{}`: {}", src, msg).into())
+}
+
+fn eval_int(src: &str, fields: &[(String, DynamicValue)]) -> Result<i128, Error> {
+    match eval(src, fields)? {
+        DynamicValue::Int(value) => Ok(value),
+        DynamicValue::Bool(value) => Ok(value.into()),
+        other => Err(format!("`{}` is {}, not an integer", src, other.kind()).into()),
+    }
+}
+
+/// Whether `field` is on the wire, like the derive's `skip`, `cond` and `since`/`until`.
+fn is_present(
+    schema: &Schema,
+    field: &FieldSchema,
+    fields: &[(String, DynamicValue)],
+) -> Result<bool, Error> {
+    let presence = &field.presence;
+    if presence.skip {
+        return Ok(false);
+    }
+    if let Some(cond) = &presence.cond {
+        match eval(cond, fields)? {
+            DynamicValue::Bool(true) => {}
+            DynamicValue::Bool(false) => return Ok(false),
+            other => return Err(format!("`{}` is {}, not a bool", cond, other.kind()).into()),
+        }
+    }
+    if let Some(version) = &schema.version
+        && (presence.since.is_some() || presence.until.is_some())
+    {
+        let version = eval_int(version, fields)?;
+        if let Some(since) = &presence.since
+            && version < eval_int(since, fields)?
+        {
+            return Ok(false);
+        }
+        if let Some(until) = &presence.until
+            && version >= eval_int(until, fields)?
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Element count of level 0 from `size_field`, if the field has one.
+fn field_size(
+    field: &FieldSchema,
+    fields: &[(String, DynamicValue)],
+) -> Result<Option<usize>, Error> {
+    match &field.size {
+        SizeSource::None => Ok(None),
+        SizeSource::Field(name) => {
+            let size = eval_int(name, fields)?;
+            usize::try_from(size)
+                .map(Some)
+                .map_err(|_| format!("size `{}` = {} is out of range", name, size).into())
+        }
+        SizeSource::Func(name) => Err(format!(
+            "`size_func = {}` cannot be called on a runtime schema",
+            name
+        )
+        .into()),
+    }
+}
+
+/// The field's [`Options`], built like the derive does from `sizes`, `aligns`, `bits` and `size`.
+fn field_opts(field: &FieldSchema, size: Option<usize>) -> Option<Options> {
+    if size.is_none() && field.sizes.is_empty() && field.aligns.is_empty() && field.bits.is_none() {
+        return None;
+    }
+    Some(Options {
+        sizes: field.sizes.clone(),
+        count: size,
+        aligns: field.aligns.clone(),
+        ctx: None,
+        bits: field.bits,
+    })
+}
+
+fn align_len(len: usize, field: &FieldSchema) -> usize {
+    match field.align {
+        Some(align) if align != 0 => len.next_multiple_of(align),
+        _ => len,
+    }
+}
+
+/// Bit position of a pointer field: its byte offset times 8 plus the base.
+fn pointer_target(
+    field: &FieldSchema,
+    fields: &[(String, DynamicValue)],
+    current: usize,
+) -> Result<Option<usize>, Error> {
+    let (target, relative) = match &field.position {
+        Position::Sequential => return Ok(None),
+        Position::OffsetField { field, relative } => (eval_int(field, fields)?, relative),
+        Position::Seek { expr, relative } => (eval_int(expr, fields)?, relative),
+    };
+    let base = match relative {
+        Relative::Start => 0,
+        Relative::Current => current,
+    };
+    Ok(usize::try_from(target)
+        .ok()
+        .and_then(|target| target.checked_mul(8))
+        .and_then(|target| target.checked_add(base)))
+}
+
+fn parse_struct<O: DynamicOrder>(
+    schema: &Schema,
+    bits: &BitSlice<u8, O>,
+) -> Result<(Vec<(String, DynamicValue)>, usize), Error> {
+    check_order::<O>(schema, "parse")?;
+    let mut fields: Vec<(String, DynamicValue)> = Vec::new();
+    let mut offset = 0;
+    let mut end = 0;
+    for field in &schema.fields {
+        let name = field.name.as_str();
+        if !is_present(schema, field, &fields).map_err(|e| e.in_field(name, offset))? {
+            continue;
+        }
+        let pointer = field.position != Position::Sequential;
+        let mut at = offset;
+        if pointer {
+            at = pointer_target(field, &fields, offset)
+                .map_err(|e| e.in_field(name, offset))?
+                .filter(|&target| target <= bits.len())
+                .ok_or_else(|| {
+                    format!(
+                        "{} parse error: offset of `{}` out of range",
+                        schema.name, name
+                    )
+                })?;
+        }
+        at += field.pad_before;
+        let rest = bits
+            .get(at..)
+            .ok_or_else(|| Error::new("not enough bits").in_field(name, at))?;
+        let opts = field_opts(
+            field,
+            field_size(field, &fields).map_err(|e| e.in_field(name, at))?,
+        );
+        let (value, l) =
+            parse_layout(&field.layout, rest, &opts).map_err(|e| e.in_field(name, at))?;
+        at += align_len(l, field) + field.pad_after;
+        if pointer {
+            end = end.max(at);
+        } else {
+            offset = at;
+        }
+        fields.push((field.name.clone(), value));
+    }
+    Ok((fields, offset.max(end)))
+}
+
+fn build_struct<O: DynamicOrder>(
+    schema: &Schema,
+    value: &DynamicValue,
+) -> Result<BitVec<u8, O>, Error> {
+    check_order::<O>(schema, "build")?;
+    let DynamicValue::Struct(values) = value else {
+        return Err(format!(
+            "{} build error: expected a struct, got {}",
+            schema.name,
+            value.kind()
+        )
+        .into());
+    };
+    let mut bv = BitVec::<u8, O>::new();
+    // Where each sequential field starts, for backfilling `offset_field`s.
+    let mut starts: Vec<(&str, usize)> = Vec::new();
+    let mut deferred: Vec<(&FieldSchema, usize)> = Vec::new();
+    for field in &schema.fields {
+        let name = field.name.as_str();
+        if !is_present(schema, field, values).map_err(|e| e.in_field(name, bv.len()))? {
+            continue;
+        }
+        if field.position != Position::Sequential {
+            let base = match field.position {
+                Position::OffsetField {
+                    relative: Relative::Current,
+                    ..
+                }
+                | Position::Seek {
+                    relative: Relative::Current,
+                    ..
+                } => bv.len(),
+                _ => 0,
+            };
+            deferred.push((field, base));
+            continue;
+        }
+        bv.resize(bv.len() + field.pad_before, false);
+        let field_bv = build_field::<O>(field, values).map_err(|e| e.in_field(name, bv.len()))?;
+        starts.push((name, bv.len()));
+        bv.extend(field_bv);
+        bv.resize(bv.len() + field.pad_after, false);
+    }
+
+    // Pointed-to data is laid out after the sequential fields, starting on a byte boundary.
+    for (field, base) in deferred {
+        let name = field.name.as_str();
+        let remainder = (bv.len() - base) % 8;
+        if remainder != 0 {
+            bv.resize(bv.len() + (8 - remainder), false);
+        }
+        let pos = match &field.position {
+            Position::Seek { .. } => {
+                let pos = pointer_target(field, values, base)
+                    .map_err(|e| e.in_field(name, bv.len()))?
+                    .filter(|&pos| pos >= bv.len())
+                    .ok_or_else(|| {
+                        format!(
+                            "{} build error: `{}` overlaps preceding data",
+                            schema.name, name
+                        )
+                    })?;
+                bv.resize(pos, false);
+                pos
+            }
+            _ => bv.len(),
+        };
+        bv.resize(bv.len() + field.pad_before, false);
+        let field_bv = build_field::<O>(field, values).map_err(|e| e.in_field(name, bv.len()))?;
+        bv.extend(field_bv);
+        bv.resize(bv.len() + field.pad_after, false);
+
+        if let Position::OffsetField {
+            field: offset_name, ..
+        } = &field.position
+        {
+            let range_msg = format!(
+                "{} build error: offset of `{}` does not fit `{}`",
+                schema.name, name, offset_name
+            );
+            let offset_field = schema.field(offset_name).ok_or_else(|| range_msg.clone())?;
+            let offset = DynamicValue::Int(((pos - base) / 8) as i128);
+            let value_bv = build_layout::<O>(
+                &offset_field.layout,
+                &offset,
+                &field_opts(offset_field, None),
+            )
+            .map_err(|_| range_msg)?;
+            let start = starts
+                .iter()
+                .find(|(name, _)| name == offset_name)
+                .map(|&(_, start)| start)
+                .ok_or_else(|| {
+                    format!(
+                        "{} build error: offset field `{}` is not on the wire",
+                        schema.name, offset_name
+                    )
+                })?;
+            bv[start..start + value_bv.len()].copy_from_bitslice(&value_bv);
+        }
+    }
+    Ok(bv)
+}
+
+/// Builds one field from its value, or from its `calc` expression if it has one.
+fn build_field<O: DynamicOrder>(
+    field: &FieldSchema,
+    values: &[(String, DynamicValue)],
+) -> Result<BitVec<u8, O>, Error> {
+    let calc;
+    let value = match &field.calc {
+        Some(src) => {
+            calc = eval(src, values)?;
+            &calc
+        }
+        None => match values.iter().find(|(name, _)| *name == field.name) {
+            Some((_, value)) => value,
+            None if matches!(field.layout, Layout::Reserved { .. }) => &DynamicValue::Reserved,
+            None => return Err("missing value".into()),
+        },
+    };
+    let mut field_bv = build_layout(&field.layout, value, &field_opts(field, None))?;
+    field_bv.resize(align_len(field_bv.len(), field), false);
+    Ok(field_bv)
+}
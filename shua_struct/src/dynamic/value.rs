@@ -0,0 +1,191 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A value parsed with a runtime [`Schema`](crate::Schema).
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+    /// Any integer, including the raw integers of fixed-point numbers and flag sets.
+    Int(i128),
+    /// A float; `f32` values are widened, which keeps every value but signaling NaN payloads.
+    Float(f64),
+    /// Reserved bits, which carry no value.
+    Reserved,
+    /// The elements of an array, `Vec` or tuple.
+    List(Vec<DynamicValue>),
+    /// Struct fields in declaration order; fields that are not on the wire are left out.
+    Struct(Vec<(String, DynamicValue)>),
+}
+
+impl DynamicValue {
+    /// The struct field `name`, if this is a struct holding it.
+    pub fn get(&self, name: &str) -> Option<&DynamicValue> {
+        match self {
+            DynamicValue::Struct(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            DynamicValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            DynamicValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value as a float; integers are converted.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            DynamicValue::Float(value) => Some(*value),
+            DynamicValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[DynamicValue]> {
+        match self {
+            DynamicValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// What kind of value this is, for error messages.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            DynamicValue::Bool(_) => "a bool",
+            DynamicValue::Int(_) => "an integer",
+            DynamicValue::Float(_) => "a float",
+            DynamicValue::Reserved => "reserved bits",
+            DynamicValue::List(_) => "a list",
+            DynamicValue::Struct(_) => "a struct",
+        }
+    }
+}
+
+// Values map onto the plain data model: lists are sequences, structs are maps
+// keyed by field name and reserved bits are unit, so JSON reads naturally.
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::DynamicValue;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::fmt;
+    use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+    use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+    impl Serialize for DynamicValue {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                DynamicValue::Bool(value) => serializer.serialize_bool(*value),
+                DynamicValue::Int(value) => {
+                    if let Ok(value) = i64::try_from(*value) {
+                        serializer.serialize_i64(value)
+                    } else if let Ok(value) = u64::try_from(*value) {
+                        serializer.serialize_u64(value)
+                    } else {
+                        serializer.serialize_i128(*value)
+                    }
+                }
+                DynamicValue::Float(value) => serializer.serialize_f64(*value),
+                DynamicValue::Reserved => serializer.serialize_unit(),
+                DynamicValue::List(items) => {
+                    let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                    for item in items {
+                        seq.serialize_element(item)?;
+                    }
+                    seq.end()
+                }
+                DynamicValue::Struct(fields) => {
+                    let mut map = serializer.serialize_map(Some(fields.len()))?;
+                    for (name, value) in fields {
+                        map.serialize_entry(name, value)?;
+                    }
+                    map.end()
+                }
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for DynamicValue {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(ValueVisitor)
+        }
+    }
+
+    struct ValueVisitor;
+
+    impl<'de> Visitor<'de> for ValueVisitor {
+        type Value = DynamicValue;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a bool, number, list, map or unit")
+        }
+
+        fn visit_bool<E>(self, value: bool) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Bool(value))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Int(value.into()))
+        }
+
+        fn visit_i128<E>(self, value: i128) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Int(value))
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Int(value.into()))
+        }
+
+        fn visit_u128<E: de::Error>(self, value: u128) -> Result<DynamicValue, E> {
+            i128::try_from(value)
+                .map(DynamicValue::Int)
+                .map_err(|_| E::custom("integer out of range"))
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Float(value))
+        }
+
+        fn visit_unit<E>(self) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Reserved)
+        }
+
+        fn visit_none<E>(self) -> Result<DynamicValue, E> {
+            Ok(DynamicValue::Reserved)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<DynamicValue, D::Error> {
+            Deserialize::deserialize(deserializer)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DynamicValue, A::Error> {
+            let mut items = Vec::new();
+            while let Some(item) = seq.next_element()? {
+                items.push(item);
+            }
+            Ok(DynamicValue::List(items))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DynamicValue, A::Error> {
+            let mut fields = Vec::new();
+            while let Some((name, value)) = map.next_entry::<String, DynamicValue>()? {
+                fields.push((name, value));
+            }
+            Ok(DynamicValue::Struct(fields))
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+/// A parse or build failure, located by field path and bit offset.
+///
+/// Errors start out at the failing value and are re-anchored by every
+/// enclosing struct and container on the way out, so the outermost caller
+/// sees a path like `players[0].skills[2].multiplier` and the bit offset
+/// from the start of its own input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Field path from the outermost value; empty if the failing value is the outermost one.
+    pub path: String,
+    /// Bit offset of the failing value from the start of the outermost value.
+    pub bit: usize,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Error {
+            path: String::new(),
+            bit: 0,
+            message: message.into(),
+        }
+    }
+
+    /// Re-anchors the error inside struct field `name`, which starts at `bit`.
+    pub fn in_field(mut self, name: &str, bit: usize) -> Self {
+        self.path = if self.path.is_empty() {
+            name.to_string()
+        } else if self.path.starts_with('[') {
+            format!("{}{}", name, self.path)
+        } else {
+            format!("{}.{}", name, self.path)
+        };
+        self.bit += bit;
+        self
+    }
+
+    /// Re-anchors the error inside element `index`, which starts at `bit`.
+    pub fn in_index(mut self, index: usize, bit: usize) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("[{}]{}", index, self.path)
+        } else {
+            format!("[{}].{}", index, self.path)
+        };
+        self.bit += bit;
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{} (at bit {})", self.message, self.bit)
+        } else {
+            write!(f, "{} (at bit {}): {}", self.path, self.bit, self.message)
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::new(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::new(message)
+    }
+}
@@ -1,90 +1,171 @@
-use crate::{BinaryField, Options};
+use crate::trace::parse_maybe_traced;
+use crate::{BinaryField, Error, Layout, Options, Span};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
 use bitvec::prelude::*;
 
+fn pad_len(len: usize, align: Option<usize>) -> usize {
+    match align {
+        Some(align) if !len.is_multiple_of(align) => align - len % align,
+        _ => 0,
+    }
+}
+
+/// Element count of a counted sequence such as `Vec`, which must come from `size_field`,
+/// `size_func` or `sizes`.
+pub(crate) fn seq_size(opts: &Option<Options>, name: &str) -> Result<usize, Error> {
+    let opts = opts
+        .as_ref()
+        .ok_or_else(|| format!("{} parse error: missing opts", name))?;
+    opts.size()
+        .ok_or_else(|| format!("{} parse error: missing size", name).into())
+}
+
+/// Parses `size` elements with the current level's alignment, handing each to `push`.
+///
+/// Shared by every sequence-like container so they all lay out elements like `Vec`.
+/// With a `span`, every element is recorded as a child span.
+pub(crate) fn parse_seq<E, O: BitOrder>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    size: usize,
+    mut span: Option<&mut Span<O>>,
+    mut parse: impl FnMut(
+        &BitSlice<u8, O>,
+        &Option<Options>,
+        Option<&mut Span<O>>,
+    ) -> Result<(E, usize), Error>,
+    mut push: impl FnMut(E) -> Result<(), Error>,
+) -> Result<usize, Error> {
+    let align = opts.as_ref().and_then(|opts| opts.align());
+    let child = opts.as_ref().and_then(|opts| opts.child());
+
+    let mut offset = 0;
+    for i in 0..size {
+        let start = offset;
+        let rest = bits
+            .get(offset..)
+            .ok_or_else(|| Error::new("not enough bits").in_index(i, offset))?;
+        let (item, l) = match span.as_deref_mut() {
+            Some(span) => span.trace_index(i, offset, rest, |span| parse(rest, &child, Some(span))),
+            None => parse(rest, &child, None),
+        }
+        .map_err(|e| e.in_index(i, offset))?;
+        offset += l;
+        offset += pad_len(offset, align);
+        push(item).map_err(|e| e.in_index(i, start))?;
+    }
+    Ok(offset)
+}
+
+/// Builds elements in order with the current level's alignment; see [`parse_seq`].
+pub(crate) fn build_seq<E, O: BitOrder>(
+    items: impl IntoIterator<Item = E>,
+    opts: &Option<Options>,
+    mut build: impl FnMut(E, &Option<Options>) -> Result<BitVec<u8, O>, Error>,
+) -> Result<BitVec<u8, O>, Error> {
+    let align = opts.as_ref().and_then(|opts| opts.align());
+    let child = opts.as_ref().and_then(|opts| opts.child());
+
+    let mut bv = BitVec::<u8, O>::new();
+    for (i, item) in items.into_iter().enumerate() {
+        bv.extend(build(item, &child).map_err(|e| e.in_index(i, bv.len()))?);
+        bv.resize(bv.len() + pad_len(bv.len(), align), false);
+    }
+    Ok(bv)
+}
+
 impl<T, O: BitOrder, const N: usize> BinaryField<O> for [T; N]
 where
     T: BinaryField<O> + Default + Copy,
 {
-    fn parse(bits: &BitSlice<u8, O>, raw_opts: &Option<Options>) -> Result<(Self, usize), String> {
-        let align = raw_opts.as_ref().and_then(|opts| opts.get_align());
-
-        let mut offset = 0;
-        let mut arr: [T; N] = [T::default(); N];
-
-        for i in 0..N {
-            let (v, l) = T::parse(&bits[offset..], raw_opts)?;
-            offset += l;
-            if let Some(align) = align {
-                let r = offset % align;
-                if r != 0 {
-                    offset += align - r;
-                }
-            }
-            arr[i] = v;
-        }
+    const C_ALIGN: usize = T::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, raw_opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        parse_array(bits, raw_opts, None)
+    }
 
-        Ok((arr, offset))
+    fn build(&self, raw_opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), raw_opts, T::build)
     }
 
-    fn build(&self, raw_opts: &Option<Options>) -> Result<BitVec<u8, O>, String> {
-        let align = raw_opts.as_ref().and_then(|opts| opts.get_align());
-
-        let mut bv = BitVec::<u8, O>::new();
-        for item in self.iter() {
-            if let Some(align) = align {
-                let r = bv.len() % align;
-                if r != 0 {
-                    bv.resize(bv.len() + (align - r), false);
-                }
-            }
-            bv.extend(item.build(raw_opts)?);
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        raw_opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        parse_array(bits, raw_opts, Some(span))
+    }
+
+    fn layout() -> Layout {
+        Layout::Array {
+            len: N,
+            item: Box::new(T::layout()),
         }
-        Ok(bv)
     }
 }
 
+fn parse_array<T, O: BitOrder, const N: usize>(
+    bits: &BitSlice<u8, O>,
+    raw_opts: &Option<Options>,
+    span: Option<&mut Span<O>>,
+) -> Result<([T; N], usize), Error>
+where
+    T: BinaryField<O> + Default + Copy,
+{
+    let mut arr: [T; N] = [T::default(); N];
+    let mut items = arr.iter_mut();
+    let offset = parse_seq(bits, raw_opts, N, span, parse_maybe_traced, |item| {
+        if let Some(slot) = items.next() {
+            *slot = item;
+        }
+        Ok(())
+    })?;
+    Ok((arr, offset))
+}
+
 impl<T, O: BitOrder> BinaryField<O> for Vec<T>
 where
     T: BinaryField<O> + Default,
 {
-    fn parse(bits: &BitSlice<u8, O>, raw_opts: &Option<Options>) -> Result<(Self, usize), String> {
-        let opts = raw_opts.as_ref().ok_or("Vec parse error: missing opts")?;
-        if opts.size == 0 {
-            return Err("Vec parse error: missing size".to_string());
-        }
-        let align = opts.get_align();
-
-        let mut vec = Vec::with_capacity(opts.size);
-        let mut offset = 0;
-
-        for _ in 0..opts.size {
-            let (item, l) = T::parse(&bits[offset..], raw_opts)?;
-            offset += l;
-            if let Some(align) = align {
-                let r = offset % align;
-                if r != 0 {
-                    offset += align - r;
-                }
-            }
-            vec.push(item);
-        }
-        Ok((vec, offset))
+    const C_ALIGN: usize = T::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, raw_opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        parse_vec(bits, raw_opts, None)
+    }
+
+    fn build(&self, raw_opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), raw_opts, T::build)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        raw_opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        parse_vec(bits, raw_opts, Some(span))
     }
 
-    fn build(&self, raw_opts: &Option<Options>) -> Result<BitVec<u8, O>, String> {
-        let opts = raw_opts.as_ref().ok_or("Vec build error: missing opts")?;
-        let align = opts.get_align();
-
-        let mut bv = BitVec::<u8, O>::new();
-        for item in self.iter() {
-            bv.extend(item.build(raw_opts)?);
-            if let Some(align) = align {
-                let r = bv.len() % align;
-                if r != 0 {
-                    bv.resize(bv.len() + (align - r), false);
-                }
-            }
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(T::layout()),
         }
-        Ok(bv)
     }
 }
+
+fn parse_vec<T: BinaryField<O>, O: BitOrder>(
+    bits: &BitSlice<u8, O>,
+    raw_opts: &Option<Options>,
+    span: Option<&mut Span<O>>,
+) -> Result<(Vec<T>, usize), Error> {
+    let size = seq_size(raw_opts, "Vec")?;
+
+    // `size` usually comes from the input itself, so don't trust it for the allocation.
+    let mut vec = Vec::with_capacity(size.min(bits.len()));
+    let offset = parse_seq(bits, raw_opts, size, span, parse_maybe_traced, |item| {
+        vec.push(item);
+        Ok(())
+    })?;
+    Ok((vec, offset))
+}
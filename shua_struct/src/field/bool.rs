@@ -1,17 +1,32 @@
-use crate::{BinaryField, Options};
+use crate::trace::parse_leaf;
+use crate::{BinaryField, Error, Layout, Options, Span};
 use bitvec::prelude::*;
 
 impl<O: BitOrder> BinaryField<O> for bool {
-    fn parse(bits: &BitSlice<u8, O>, _opts: &Option<Options>) -> Result<(Self, usize), String> {
-        if bits.len() < 1 {
-            return Err("bool parse error: not enough bits".to_string());
+    const C_ALIGN: usize = 1;
+
+    fn parse(bits: &BitSlice<u8, O>, _opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        if bits.is_empty() {
+            return Err("bool parse error: not enough bits".into());
         }
         Ok((bits[0], 1))
     }
 
-    fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, O>, String> {
+    fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
         let mut bv = BitVec::<u8, O>::new();
         bv.push(*self);
         Ok(bv)
     }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        parse_leaf(bits, opts, span)
+    }
+
+    fn layout() -> Layout {
+        Layout::Bool
+    }
 }
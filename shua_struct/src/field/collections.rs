@@ -0,0 +1,424 @@
+use crate::field::array::{build_seq, parse_seq, seq_size};
+use crate::trace::parse_maybe_traced;
+use crate::{BinaryField, Error, Layout, Options, Span};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use bitvec::prelude::*;
+
+/// Transparent: the boxed value uses the same options as if it were unboxed.
+impl<T, O: BitOrder> BinaryField<O> for Box<T>
+where
+    T: BinaryField<O>,
+{
+    const C_ALIGN: usize = T::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        let (value, l) = T::parse(bits, opts)?;
+        Ok((Box::new(value), l))
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        (**self).build(opts)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        let (value, l) = T::parse_traced(bits, opts, span)?;
+        Ok((Box::new(value), l))
+    }
+
+    fn layout() -> Layout {
+        T::layout()
+    }
+}
+
+impl<T, O: BitOrder> BinaryField<O> for Box<[T]>
+where
+    T: BinaryField<O>,
+{
+    const C_ALIGN: usize = T::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        let (vec, l) = collect_seq(bits, opts, "Box<[T]>", None, Vec::with_capacity, Vec::push)?;
+        Ok((vec.into_boxed_slice(), l))
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), opts, T::build)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        let (vec, l) = collect_seq(
+            bits,
+            opts,
+            "Box<[T]>",
+            Some(span),
+            Vec::with_capacity,
+            Vec::push,
+        )?;
+        Ok((vec.into_boxed_slice(), l))
+    }
+
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(T::layout()),
+        }
+    }
+}
+
+impl<T, O: BitOrder> BinaryField<O> for VecDeque<T>
+where
+    T: BinaryField<O>,
+{
+    const C_ALIGN: usize = T::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        collect_seq(
+            bits,
+            opts,
+            "VecDeque",
+            None,
+            VecDeque::with_capacity,
+            VecDeque::push_back,
+        )
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), opts, T::build)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        collect_seq(
+            bits,
+            opts,
+            "VecDeque",
+            Some(span),
+            VecDeque::with_capacity,
+            VecDeque::push_back,
+        )
+    }
+
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(T::layout()),
+        }
+    }
+}
+
+/// Parses `size` elements into a collection created with capacity for them.
+fn collect_seq<C, T, O>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    name: &str,
+    span: Option<&mut Span<O>>,
+    with_capacity: impl FnOnce(usize) -> C,
+    mut push: impl FnMut(&mut C, T),
+) -> Result<(C, usize), Error>
+where
+    T: BinaryField<O>,
+    O: BitOrder,
+{
+    let size = seq_size(opts, name)?;
+    // `size` usually comes from the input itself, so don't trust it for the allocation.
+    let mut collection = with_capacity(size.min(bits.len()));
+    let l = parse_seq(bits, opts, size, span, parse_maybe_traced, |item| {
+        push(&mut collection, item);
+        Ok(())
+    })?;
+    Ok((collection, l))
+}
+
+/// Parses `size` key/value entries into a map, rejecting duplicate keys.
+fn collect_map<M, K, V, O>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    name: &str,
+    span: Option<&mut Span<O>>,
+    mut map: M,
+    mut insert: impl FnMut(&mut M, K, V) -> Option<V>,
+) -> Result<(M, usize), Error>
+where
+    K: BinaryField<O>,
+    V: BinaryField<O>,
+    O: BitOrder,
+{
+    let size = seq_size(opts, name)?;
+    let l = parse_seq(
+        bits,
+        opts,
+        size,
+        span,
+        parse_entry,
+        |(key, value)| match insert(&mut map, key, value) {
+            None => Ok(()),
+            Some(_) => Err(alloc::format!("{} parse error: duplicate key", name).into()),
+        },
+    )?;
+    Ok((map, l))
+}
+
+/// Parses a key immediately followed by its value; both get the entry's options.
+fn parse_entry<K, V, O>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    mut span: Option<&mut Span<O>>,
+) -> Result<((K, V), usize), Error>
+where
+    K: BinaryField<O>,
+    V: BinaryField<O>,
+    O: BitOrder,
+{
+    let (key, key_len) = match span.as_deref_mut() {
+        Some(span) => span.trace_field("key", 0, bits, |span| K::parse_traced(bits, opts, span)),
+        None => K::parse(bits, opts),
+    }
+    .map_err(|e| e.in_field("key", 0))?;
+    let rest = bits
+        .get(key_len..)
+        .ok_or_else(|| Error::new("not enough bits").in_field("value", key_len))?;
+    let (value, value_len) = match span {
+        Some(span) => span.trace_field("value", key_len, rest, |span| {
+            V::parse_traced(rest, opts, span)
+        }),
+        None => V::parse(rest, opts),
+    }
+    .map_err(|e| e.in_field("value", key_len))?;
+    Ok(((key, value), key_len + value_len))
+}
+
+fn build_entry<K, V, O>(key: &K, value: &V, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error>
+where
+    K: BinaryField<O>,
+    V: BinaryField<O>,
+    O: BitOrder,
+{
+    let mut bv = key.build(opts).map_err(|e| e.in_field("key", 0))?;
+    let key_len = bv.len();
+    bv.extend(
+        value
+            .build(opts)
+            .map_err(|e| e.in_field("value", key_len))?,
+    );
+    Ok(bv)
+}
+
+/// The `C_ALIGN` of a `(K, V)` tuple, which needs the `tuple` feature.
+const fn entry_c_align<K: BinaryField<O>, V: BinaryField<O>, O: BitOrder>() -> usize {
+    let mut align = 8;
+    if K::C_ALIGN > align {
+        align = K::C_ALIGN;
+    }
+    if V::C_ALIGN > align {
+        align = V::C_ALIGN;
+    }
+    align
+}
+
+/// A sequence of key/value entries; duplicate keys are rejected so the entry count round-trips.
+impl<K, V, O: BitOrder> BinaryField<O> for BTreeMap<K, V>
+where
+    K: BinaryField<O> + Ord,
+    V: BinaryField<O>,
+{
+    /// Aligned like the `(K, V)` tuple each entry is built as.
+    const C_ALIGN: usize = entry_c_align::<K, V, O>();
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        collect_map(
+            bits,
+            opts,
+            "BTreeMap",
+            None,
+            BTreeMap::new(),
+            BTreeMap::insert,
+        )
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), opts, |(key, value), opts| {
+            build_entry(key, value, opts)
+        })
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        collect_map(
+            bits,
+            opts,
+            "BTreeMap",
+            Some(span),
+            BTreeMap::new(),
+            BTreeMap::insert,
+        )
+    }
+
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(Layout::Tuple(vec![K::layout(), V::layout()])),
+        }
+    }
+}
+
+/// Like `BTreeMap`, but entries are built in the map's iteration order.
+#[cfg(feature = "std")]
+impl<K, V, S, O: BitOrder> BinaryField<O> for std::collections::HashMap<K, V, S>
+where
+    K: BinaryField<O> + Eq + core::hash::Hash,
+    V: BinaryField<O>,
+    S: core::hash::BuildHasher + Default,
+{
+    /// Aligned like the `(K, V)` tuple each entry is built as.
+    const C_ALIGN: usize = entry_c_align::<K, V, O>();
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        collect_map(bits, opts, "HashMap", None, Self::default(), Self::insert)
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), opts, |(key, value), opts| {
+            build_entry(key, value, opts)
+        })
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        collect_map(
+            bits,
+            opts,
+            "HashMap",
+            Some(span),
+            Self::default(),
+            Self::insert,
+        )
+    }
+
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(Layout::Tuple(vec![K::layout(), V::layout()])),
+        }
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<A, O: BitOrder> BinaryField<O> for smallvec::SmallVec<A>
+where
+    A: smallvec::Array,
+    A::Item: BinaryField<O>,
+{
+    const C_ALIGN: usize = A::Item::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        collect_seq(
+            bits,
+            opts,
+            "SmallVec",
+            None,
+            Self::with_capacity,
+            Self::push,
+        )
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), opts, A::Item::build)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        collect_seq(
+            bits,
+            opts,
+            "SmallVec",
+            Some(span),
+            Self::with_capacity,
+            Self::push,
+        )
+    }
+
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(A::Item::layout()),
+        }
+    }
+}
+
+/// Fails to parse more than `CAP` elements instead of allocating.
+#[cfg(feature = "arrayvec")]
+impl<T, O: BitOrder, const CAP: usize> BinaryField<O> for arrayvec::ArrayVec<T, CAP>
+where
+    T: BinaryField<O>,
+{
+    const C_ALIGN: usize = T::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        parse_array_vec(bits, opts, None)
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        build_seq(self.iter(), opts, T::build)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        parse_array_vec(bits, opts, Some(span))
+    }
+
+    fn layout() -> Layout {
+        Layout::Seq {
+            item: Box::new(T::layout()),
+        }
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+fn parse_array_vec<T, O, const CAP: usize>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    span: Option<&mut Span<O>>,
+) -> Result<(arrayvec::ArrayVec<T, CAP>, usize), Error>
+where
+    T: BinaryField<O>,
+    O: BitOrder,
+{
+    let size = seq_size(opts, "ArrayVec")?;
+    if size > CAP {
+        return Err(alloc::format!(
+            "ArrayVec parse error: size {} exceeds capacity {}",
+            size,
+            CAP
+        )
+        .into());
+    }
+    collect_seq(
+        bits,
+        opts,
+        "ArrayVec",
+        span,
+        |_| arrayvec::ArrayVec::new(),
+        |vec, item| vec.push(item),
+    )
+}
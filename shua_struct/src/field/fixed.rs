@@ -0,0 +1,136 @@
+use crate::{BinaryField, Error, Layout, Options, Span};
+use bitvec::prelude::*;
+use core::fmt;
+
+/// A binary fixed-point number: the integer `I` scaled by `2^-FRAC`.
+///
+/// The sign comes from `I`, so `Fixed<i16, 15>` is Q15 and `Fixed<u16, 8>` is UQ8.8.
+/// On the wire it is exactly its raw integer, including any `bits` option.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<I, const FRAC: u32>(pub I);
+
+/// Q15: signed, 15 fractional bits.
+pub type Q15 = Fixed<i16, 15>;
+/// Q31: signed, 31 fractional bits.
+pub type Q31 = Fixed<i32, 31>;
+/// Q16.16: signed, 16 integer and 16 fractional bits.
+pub type Q16_16 = Fixed<i32, 16>;
+/// UQ8.8: unsigned, 8 integer and 8 fractional bits.
+pub type UQ8_8 = Fixed<u16, 8>;
+/// UQ16.16: unsigned, 16 integer and 16 fractional bits.
+pub type UQ16_16 = Fixed<u32, 16>;
+
+/// Integers usable as the raw value of a [`Fixed`].
+pub trait FixedRaw: Copy {
+    fn to_f64(self) -> f64;
+    /// Converts with saturation at the integer's bounds.
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_fixed_raw {
+    ($($t:ty),*) => {
+        $(
+            impl FixedRaw for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn from_f64(value: f64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_raw!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<I: FixedRaw, const FRAC: u32> Fixed<I, FRAC> {
+    const SCALE: f64 = (1u128 << FRAC) as f64;
+
+    pub const fn from_bits(raw: I) -> Self {
+        Fixed(raw)
+    }
+
+    pub const fn to_bits(self) -> I {
+        self.0
+    }
+
+    /// Rounds to the nearest representable value, saturating at the bounds of `I`.
+    pub fn from_f64(value: f64) -> Self {
+        let scaled = value * Self::SCALE;
+        let rounded = if scaled < 0.0 {
+            scaled - 0.5
+        } else {
+            scaled + 0.5
+        };
+        Fixed(I::from_f64(rounded))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0.to_f64() / Self::SCALE
+    }
+
+    /// Rounds to the nearest representable value, saturating at the bounds of `I`.
+    pub fn from_f32(value: f32) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+}
+
+impl<I: FixedRaw, const FRAC: u32> From<Fixed<I, FRAC>> for f64 {
+    fn from(value: Fixed<I, FRAC>) -> f64 {
+        value.to_f64()
+    }
+}
+
+impl<I: FixedRaw, const FRAC: u32> From<Fixed<I, FRAC>> for f32 {
+    fn from(value: Fixed<I, FRAC>) -> f32 {
+        value.to_f32()
+    }
+}
+
+impl<I: FixedRaw, const FRAC: u32> fmt::Display for Fixed<I, FRAC> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl<I, O: BitOrder, const FRAC: u32> BinaryField<O> for Fixed<I, FRAC>
+where
+    I: BinaryField<O>,
+{
+    const C_ALIGN: usize = I::C_ALIGN;
+
+    fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        let (raw, l) = I::parse(bits, opts)?;
+        Ok((Fixed(raw), l))
+    }
+
+    fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        self.0.build(opts)
+    }
+
+    fn parse_traced(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        let (raw, l) = I::parse_traced(bits, opts, span)?;
+        Ok((Fixed(raw), l))
+    }
+
+    fn layout() -> Layout {
+        match I::layout() {
+            Layout::Int { signed, bits } => Layout::Fixed {
+                signed,
+                bits,
+                frac: FRAC,
+            },
+            layout => layout,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{BinaryField, Error, Layout, Options};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use bitflags::Flags;
+use bitvec::prelude::*;
+
+/// How bits that match no declared flag are treated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownBits {
+    /// Keep them, so values round-trip unchanged.
+    #[default]
+    Retain,
+    /// Fail on parse and build.
+    Reject,
+    /// Drop them.
+    Truncate,
+}
+
+/// Parses a flag set as its raw bits integer, honoring the `bits` option for sub-byte widths.
+///
+/// This backs `#[binary_struct(flags)]`; it is public for hand-written impls.
+pub fn parse<F, O>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    unknown: UnknownBits,
+) -> Result<(F, usize), Error>
+where
+    F: Flags,
+    F::Bits: BinaryField<O>,
+    O: BitOrder,
+{
+    let (raw, l) = F::Bits::parse(bits, opts)?;
+    let flags = match unknown {
+        UnknownBits::Retain => F::from_bits_retain(raw),
+        UnknownBits::Truncate => F::from_bits_truncate(raw),
+        UnknownBits::Reject => F::from_bits(raw).ok_or_else(|| {
+            format!(
+                "{} parse error: unknown flag bits",
+                core::any::type_name::<F>()
+            )
+        })?,
+    };
+    Ok((flags, l))
+}
+
+/// Builds a flag set as its raw bits integer; see [`parse`].
+pub fn build<F, O>(
+    flags: &F,
+    opts: &Option<Options>,
+    unknown: UnknownBits,
+) -> Result<BitVec<u8, O>, Error>
+where
+    F: Flags,
+    F::Bits: BinaryField<O>,
+    O: BitOrder,
+{
+    let raw = match unknown {
+        UnknownBits::Retain => flags.bits(),
+        UnknownBits::Truncate => F::from_bits_truncate(flags.bits()).bits(),
+        UnknownBits::Reject => F::from_bits(flags.bits())
+            .ok_or_else(|| {
+                format!(
+                    "{} build error: unknown flag bits",
+                    core::any::type_name::<F>()
+                )
+            })?
+            .bits(),
+    };
+    raw.build(opts)
+}
+
+/// C alignment of a flag set: that of its bits integer.
+pub const fn c_align<F, O>() -> usize
+where
+    F: Flags,
+    F::Bits: BinaryField<O>,
+    O: BitOrder,
+{
+    F::Bits::C_ALIGN
+}
+
+/// Describes a flag set as its raw bits integer plus its named flags; see [`parse`].
+///
+/// `bits` is the type's default width, if narrower than its bits integer. Flags whose
+/// value does not fit a `u64` (negative values of signed types) are left out.
+pub fn layout<F, O>(bits: Option<usize>) -> Layout
+where
+    F: Flags,
+    F::Bits: BinaryField<O> + TryInto<u64>,
+    O: BitOrder,
+{
+    let mut repr = F::Bits::layout();
+    if let (Layout::Int { bits: width, .. }, Some(bits)) = (&mut repr, bits) {
+        *width = bits;
+    }
+    Layout::Flags {
+        repr: Box::new(repr),
+        flags: F::FLAGS
+            .iter()
+            .filter_map(|flag| {
+                let value = flag.value().bits().try_into().ok()?;
+                Some((flag.name().to_string(), value))
+            })
+            .collect(),
+    }
+}
@@ -1,61 +1,107 @@
-use crate::{BinaryField, Options};
+use crate::trace::parse_leaf;
+use crate::{BinaryField, Error, FloatKind, Layout, Options, Span};
+use alloc::format;
 use bitvec::prelude::*;
 
 macro_rules! impl_bit_float {
-    ($t:ty, $int:ty, $size_bits:expr) => {
+    ($t:ty, $int:ty, $size_bits:expr, $kind:ident) => {
+        /// Stored as its raw bits, little-endian. Values only pass through
+        /// `from_bits`/`to_bits`, so NaN payloads, signaling NaNs included,
+        /// round-trip bit-exactly without any option.
         impl BinaryField<Lsb0> for $t {
+            const C_ALIGN: usize = $size_bits;
+
             fn parse(
                 bits: &BitSlice<u8, Lsb0>,
                 _opts: &Option<Options>,
-            ) -> Result<(Self, usize), String> {
+            ) -> Result<(Self, usize), Error> {
                 if bits.len() < $size_bits {
                     return Err(format!(
                         "{} parse error: not enough bits (needed {}, got {})",
                         stringify!($t),
                         $size_bits,
                         bits.len()
-                    ));
+                    )
+                    .into());
                 }
                 let raw_bits = bits[0..$size_bits].load_le::<$int>();
                 Ok((<$t>::from_bits(raw_bits), $size_bits))
             }
 
-            fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, Lsb0>, String> {
+            fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, Lsb0>, Error> {
                 let mut bv = BitVec::<u8, Lsb0>::new();
                 let bytes = self.to_bits().to_le_bytes();
                 bv.extend_from_raw_slice(&bytes);
                 bv.truncate($size_bits);
                 Ok(bv)
             }
+
+            fn parse_traced(
+                bits: &BitSlice<u8, Lsb0>,
+                opts: &Option<Options>,
+                span: &mut Span<Lsb0>,
+            ) -> Result<(Self, usize), Error> {
+                parse_leaf(bits, opts, span)
+            }
+
+            fn layout() -> Layout {
+                Layout::Float {
+                    bits: $size_bits,
+                    kind: FloatKind::$kind,
+                }
+            }
         }
 
+        /// Stored as its raw bits, big-endian; NaN payloads round-trip as for `Lsb0`.
         impl BinaryField<Msb0> for $t {
+            const C_ALIGN: usize = $size_bits;
+
             fn parse(
                 bits: &BitSlice<u8, Msb0>,
                 _opts: &Option<Options>,
-            ) -> Result<(Self, usize), String> {
+            ) -> Result<(Self, usize), Error> {
                 if bits.len() < $size_bits {
                     return Err(format!(
                         "{} parse error: not enough bits (needed {}, got {})",
                         stringify!($t),
                         $size_bits,
                         bits.len()
-                    ));
+                    )
+                    .into());
                 }
                 let raw_bits = bits[0..$size_bits].load_be::<$int>();
                 Ok((<$t>::from_bits(raw_bits), $size_bits))
             }
 
-            fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, Msb0>, String> {
+            fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, Msb0>, Error> {
                 let mut bv = BitVec::<u8, Msb0>::new();
                 let bytes = self.to_bits().to_be_bytes();
                 bv.extend_from_raw_slice(&bytes);
                 bv.truncate($size_bits);
                 Ok(bv)
             }
+
+            fn parse_traced(
+                bits: &BitSlice<u8, Msb0>,
+                opts: &Option<Options>,
+                span: &mut Span<Msb0>,
+            ) -> Result<(Self, usize), Error> {
+                parse_leaf(bits, opts, span)
+            }
+
+            fn layout() -> Layout {
+                Layout::Float {
+                    bits: $size_bits,
+                    kind: FloatKind::$kind,
+                }
+            }
         }
     };
 }
 
-impl_bit_float!(f32, u32, 32);
-impl_bit_float!(f64, u64, 64);
+impl_bit_float!(f32, u32, 32, Ieee);
+impl_bit_float!(f64, u64, 64, Ieee);
+#[cfg(feature = "half")]
+impl_bit_float!(half::f16, u16, 16, Ieee);
+#[cfg(feature = "half")]
+impl_bit_float!(half::bf16, u16, 16, Bfloat);
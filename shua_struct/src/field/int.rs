@@ -1,58 +1,145 @@
-use crate::{BinaryField, Options};
+use crate::trace::parse_leaf;
+use crate::{BinaryField, Error, Layout, Options, Span};
+use alloc::format;
 use bitvec::prelude::*;
 
+/// Wire width of an integer-backed value: the `bits` option if given, else `max_bits`.
+pub(crate) fn bit_width(
+    opts: &Option<Options>,
+    max_bits: usize,
+    name: &str,
+) -> Result<usize, Error> {
+    match opts.as_ref().and_then(|opts| opts.bits) {
+        None => Ok(max_bits),
+        Some(bits) if bits > 0 && bits <= max_bits => Ok(bits),
+        Some(bits) => Err(format!(
+            "{} error: bit width {} out of range (1..={})",
+            name, bits, max_bits
+        )
+        .into()),
+    }
+}
+
 macro_rules! impl_bit_primitive {
     ($t:ty, $size_bits:expr) => {
         impl BinaryField<Lsb0> for $t {
+            const C_ALIGN: usize = $size_bits;
+
             fn parse(
                 bits: &BitSlice<u8, Lsb0>,
-                _opts: &Option<Options>,
-            ) -> Result<(Self, usize), String> {
-                if bits.len() < $size_bits {
+                opts: &Option<Options>,
+            ) -> Result<(Self, usize), Error> {
+                let width = bit_width(opts, $size_bits, stringify!($t))?;
+                if bits.len() < width {
                     return Err(format!(
                         "{} parse error: not enough bits (needed {}, got {})",
                         stringify!($t),
-                        $size_bits,
+                        width,
                         bits.len()
-                    ));
+                    )
+                    .into());
                 }
-                let value = bits[0..$size_bits].load_le::<$t>();
-                Ok((value, $size_bits))
+                let value = bits[0..width].load_le::<$t>();
+                Ok((value, width))
             }
 
-            fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, Lsb0>, String> {
+            fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, Lsb0>, Error> {
+                let width = bit_width(opts, $size_bits, stringify!($t))?;
+                if width < $size_bits {
+                    let mut bv = BitVec::<u8, Lsb0>::repeat(false, width);
+                    bv.store_le(*self);
+                    if bv.load_le::<$t>() != *self {
+                        return Err(format!(
+                            "{} build error: {} does not fit in {} bits",
+                            stringify!($t),
+                            self,
+                            width
+                        )
+                        .into());
+                    }
+                    return Ok(bv);
+                }
                 let mut bv = BitVec::<u8, Lsb0>::new();
                 let bytes = self.to_le_bytes();
                 bv.extend_from_raw_slice(&bytes);
                 bv.truncate($size_bits);
                 Ok(bv)
             }
+
+            fn parse_traced(
+                bits: &BitSlice<u8, Lsb0>,
+                opts: &Option<Options>,
+                span: &mut Span<Lsb0>,
+            ) -> Result<(Self, usize), Error> {
+                parse_leaf(bits, opts, span)
+            }
+
+            fn layout() -> Layout {
+                Layout::Int {
+                    signed: <$t>::MIN != 0,
+                    bits: $size_bits,
+                }
+            }
         }
 
         impl BinaryField<Msb0> for $t {
+            const C_ALIGN: usize = $size_bits;
+
             fn parse(
                 bits: &BitSlice<u8, Msb0>,
-                _opts: &Option<Options>,
-            ) -> Result<(Self, usize), String> {
-                if bits.len() < $size_bits {
+                opts: &Option<Options>,
+            ) -> Result<(Self, usize), Error> {
+                let width = bit_width(opts, $size_bits, stringify!($t))?;
+                if bits.len() < width {
                     return Err(format!(
                         "{} parse error: not enough bits (needed {}, got {})",
                         stringify!($t),
-                        $size_bits,
+                        width,
                         bits.len()
-                    ));
+                    )
+                    .into());
                 }
-                let value = bits[0..$size_bits].load_be::<$t>();
-                Ok((value, $size_bits))
+                let value = bits[0..width].load_be::<$t>();
+                Ok((value, width))
             }
 
-            fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, Msb0>, String> {
+            fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, Msb0>, Error> {
+                let width = bit_width(opts, $size_bits, stringify!($t))?;
+                if width < $size_bits {
+                    let mut bv = BitVec::<u8, Msb0>::repeat(false, width);
+                    bv.store_be(*self);
+                    if bv.load_be::<$t>() != *self {
+                        return Err(format!(
+                            "{} build error: {} does not fit in {} bits",
+                            stringify!($t),
+                            self,
+                            width
+                        )
+                        .into());
+                    }
+                    return Ok(bv);
+                }
                 let mut bv = BitVec::<u8, Msb0>::new();
                 let bytes = self.to_be_bytes();
                 bv.extend_from_raw_slice(&bytes);
                 bv.truncate($size_bits);
                 Ok(bv)
             }
+
+            fn parse_traced(
+                bits: &BitSlice<u8, Msb0>,
+                opts: &Option<Options>,
+                span: &mut Span<Msb0>,
+            ) -> Result<(Self, usize), Error> {
+                parse_leaf(bits, opts, span)
+            }
+
+            fn layout() -> Layout {
+                Layout::Int {
+                    signed: <$t>::MIN != 0,
+                    bits: $size_bits,
+                }
+            }
         }
     };
 }
@@ -1,6 +1,12 @@
 #[cfg(feature = "array")]
 pub mod array;
 
+#[cfg(feature = "collections")]
+pub mod collections;
+
+#[cfg(feature = "tuple")]
+pub mod tuple;
+
 #[cfg(feature = "bool")]
 pub mod bool;
 
@@ -8,4 +14,13 @@ pub mod bool;
 pub mod int;
 
 #[cfg(feature = "float")]
-pub mod float;
\ No newline at end of file
+pub mod float;
+
+#[cfg(feature = "reserved")]
+pub mod reserved;
+
+#[cfg(feature = "fixed")]
+pub mod fixed;
+
+#[cfg(feature = "bitflags")]
+pub mod flags;
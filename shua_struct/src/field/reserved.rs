@@ -0,0 +1,43 @@
+use crate::{BinaryField, Error, Layout, Options};
+use alloc::format;
+use bitvec::prelude::*;
+
+/// `BITS` reserved bits that are always written as zeros.
+///
+/// With `STRICT = true`, parsing fails if any of the bits is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reserved<const BITS: usize, const STRICT: bool = false>;
+
+impl<O: BitOrder, const BITS: usize, const STRICT: bool> BinaryField<O> for Reserved<BITS, STRICT> {
+    const C_ALIGN: usize = 1;
+
+    fn parse(bits: &BitSlice<u8, O>, _opts: &Option<Options>) -> Result<(Self, usize), Error> {
+        if bits.len() < BITS {
+            return Err(format!(
+                "Reserved parse error: not enough bits (needed {}, got {})",
+                BITS,
+                bits.len()
+            )
+            .into());
+        }
+        if STRICT && bits[..BITS].any() {
+            return Err(format!(
+                "Reserved parse error: non-zero bits in {} reserved bits",
+                BITS
+            )
+            .into());
+        }
+        Ok((Reserved, BITS))
+    }
+
+    fn build(&self, _opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+        Ok(BitVec::repeat(false, BITS))
+    }
+
+    fn layout() -> Layout {
+        Layout::Reserved {
+            bits: BITS,
+            strict: STRICT,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{BinaryField, Error, Layout, Options, Span};
+use alloc::vec;
+use bitvec::prelude::*;
+
+/// Parses the member `index` starting at `offset`, recording it in `span` if given.
+fn parse_member<T: BinaryField<O>, O: BitOrder>(
+    bits: &BitSlice<u8, O>,
+    offset: usize,
+    index: &str,
+    opts: &Option<Options>,
+    span: Option<&mut Span<O>>,
+) -> Result<(T, usize), Error> {
+    let rest = bits
+        .get(offset..)
+        .ok_or_else(|| Error::new("tuple parse error: not enough bits").in_field(index, offset))?;
+    match span {
+        Some(span) => span.trace_field(index, offset, rest, |span| {
+            T::parse_traced(rest, opts, span)
+        }),
+        None => T::parse(rest, opts),
+    }
+    .map_err(|e| e.in_field(index, offset))
+}
+
+/// Elements are laid out back to back, each parsed and built with the tuple's options.
+macro_rules! impl_tuple {
+    ($(($t:ident, $idx:tt)),+) => {
+        impl<O: BitOrder, $($t),+> BinaryField<O> for ($($t,)+)
+        where
+            $($t: BinaryField<O>),+
+        {
+            const C_ALIGN: usize = {
+                let mut align = 8;
+                $(if $t::C_ALIGN > align {
+                    align = $t::C_ALIGN;
+                })+
+                align
+            };
+
+            fn parse(bits: &BitSlice<u8, O>, opts: &Option<Options>) -> Result<(Self, usize), Error> {
+                let mut offset = 0;
+                let value = ($({
+                    let (item, l) = parse_member::<$t, O>(
+                        bits,
+                        offset,
+                        stringify!($idx),
+                        opts,
+                        None,
+                    )?;
+                    offset += l;
+                    item
+                },)+);
+                Ok((value, offset))
+            }
+
+            fn build(&self, opts: &Option<Options>) -> Result<BitVec<u8, O>, Error> {
+                let mut bv = BitVec::<u8, O>::new();
+                $(
+                    let start = bv.len();
+                    bv.extend(
+                        self.$idx
+                            .build(opts)
+                            .map_err(|e| e.in_field(stringify!($idx), start))?,
+                    );
+                )+
+                Ok(bv)
+            }
+
+            fn parse_traced(
+                bits: &BitSlice<u8, O>,
+                opts: &Option<Options>,
+                span: &mut Span<O>,
+            ) -> Result<(Self, usize), Error> {
+                let mut offset = 0;
+                let value = ($({
+                    let (item, l) = parse_member::<$t, O>(
+                        bits,
+                        offset,
+                        stringify!($idx),
+                        opts,
+                        Some(&mut *span),
+                    )?;
+                    offset += l;
+                    item
+                },)+);
+                Ok((value, offset))
+            }
+
+            fn layout() -> Layout {
+                Layout::Tuple(vec![$($t::layout()),+])
+            }
+        }
+    };
+}
+
+impl_tuple!((A, 0));
+impl_tuple!((A, 0), (B, 1));
+impl_tuple!((A, 0), (B, 1), (C, 2));
+impl_tuple!((A, 0), (B, 1), (C, 2), (D, 3));
+impl_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4));
+impl_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5));
+impl_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6));
+impl_tuple!(
+    (A, 0),
+    (B, 1),
+    (C, 2),
+    (D, 3),
+    (E, 4),
+    (F, 5),
+    (G, 6),
+    (H, 7)
+);
+impl_tuple!(
+    (A, 0),
+    (B, 1),
+    (C, 2),
+    (D, 3),
+    (E, 4),
+    (F, 5),
+    (G, 6),
+    (H, 7),
+    (I, 8)
+);
+impl_tuple!(
+    (A, 0),
+    (B, 1),
+    (C, 2),
+    (D, 3),
+    (E, 4),
+    (F, 5),
+    (G, 6),
+    (H, 7),
+    (I, 8),
+    (J, 9)
+);
+impl_tuple!(
+    (A, 0),
+    (B, 1),
+    (C, 2),
+    (D, 3),
+    (E, 4),
+    (F, 5),
+    (G, 6),
+    (H, 7),
+    (I, 8),
+    (J, 9),
+    (K, 10)
+);
+impl_tuple!(
+    (A, 0),
+    (B, 1),
+    (C, 2),
+    (D, 3),
+    (E, 4),
+    (F, 5),
+    (G, 6),
+    (H, 7),
+    (I, 8),
+    (J, 9),
+    (K, 10),
+    (L, 11)
+);
@@ -0,0 +1,33 @@
+use crate::{BinaryField, Options};
+use bitvec::prelude::*;
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+/// Reads `reader` to the end and parses a `T` from the collected bytes.
+pub fn read_from<T, O, R>(reader: &mut R, opts: &Option<Options>) -> io::Result<T>
+where
+    T: BinaryField<O>,
+    O: BitOrder,
+    R: Read,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let bits = BitSlice::<u8, O>::from_slice(&bytes);
+    let (value, _) =
+        T::parse(bits, opts).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(value)
+}
+
+/// Builds `value` and writes it to `writer`, zero-padding the last byte.
+pub fn write_to<T, O, W>(value: &T, writer: &mut W, opts: &Option<Options>) -> io::Result<()>
+where
+    T: BinaryField<O>,
+    O: BitOrder,
+    W: Write,
+{
+    let mut bv = value
+        .build(opts)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    bv.set_uninitialized(false);
+    writer.write_all(bv.as_raw_slice())
+}
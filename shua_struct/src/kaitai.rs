@@ -0,0 +1,815 @@
+//! [Kaitai Struct](https://kaitai.io) `.ksy` export for derived types, generated from their [`Schema`].
+//!
+//! The generated document describes the same bits as the derive: nested structs
+//! become user types, containers become repeated attributes, `bits`, `pad_*`,
+//! `align` and `sub_align` become bit-sized attributes or padding, and the bit
+//! order sets both `endian` and `bit-endian`. `offset_field`/`seek` fields become
+//! `pos` instances. Kaitai resolves `pos` against the start of the stream, so
+//! offsets into a nested struct, and `relative_to = "current"` offsets, are added
+//! to a `*_pos` value instance that a zero-size `seq` attribute captures from
+//! `_io.pos` where the derive measures them from.
+//!
+//! What Kaitai cannot express (`parse_with` fields, padding after a
+//! data-dependent width that is not a whole number of bytes) is kept as a `doc`
+//! note on the attribute. Containers whose element count Kaitai cannot compute
+//! (`size_func` counts, missing sizes) become an attribute that always fails
+//! validation, so parsing stops there instead of misreading the fields after it.
+//!
+//! ```ignore
+//! std::fs::write("packet.ksy", shua_struct::kaitai::ksy(&Packet::schema()))?;
+//! ```
+
+use crate::schema::{
+    FieldSchema, FloatKind, Layout, Position, Relative, Schema, SizeSource, item_width,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Renders `schema` as a `.ksy` document, with every struct nested in it as a user type.
+pub fn ksy(schema: &Schema) -> String {
+    let mut ksy = Ksy {
+        types: Vec::new(),
+        done: Vec::new(),
+    };
+    let root = ksy.struct_type(schema, true);
+    let id = type_id(&schema.name);
+
+    let mut out = String::new();
+    let order = endian(&schema.bit_order);
+    let _ = write!(
+        out,
+        "meta:\n  id: {}\n  endian: {}\n  bit-endian: {}\n",
+        id, order, order
+    );
+    push_type_body(&mut out, &root, 0);
+    if !ksy.types.is_empty() {
+        out.push_str("types:\n");
+        for (id, ty) in &ksy.types {
+            let _ = writeln!(out, "  {}:", id);
+            push_type_body(&mut out, ty, 4);
+        }
+    }
+    out
+}
+
+/// A Kaitai user type.
+#[derive(Default)]
+struct Type {
+    doc: String,
+    seq: Vec<Attr>,
+    instances: Vec<Attr>,
+}
+
+/// A `seq` attribute or instance: its id and its keys in output order.
+struct Attr {
+    id: String,
+    keys: Vec<(&'static str, String)>,
+    /// A `valid: expr` check, written after the keys.
+    valid: Option<String>,
+}
+
+impl Attr {
+    fn new(id: impl Into<String>) -> Self {
+        Attr {
+            id: id.into(),
+            keys: Vec::new(),
+            valid: None,
+        }
+    }
+
+    fn key(&mut self, key: &'static str, value: impl Into<String>) {
+        self.keys.push((key, value.into()));
+    }
+
+    /// Appends to the attribute's `doc`.
+    fn note(&mut self, note: &str) {
+        match self.keys.iter_mut().find(|(key, _)| *key == "doc") {
+            Some((_, doc)) => {
+                doc.push_str("; ");
+                doc.push_str(note);
+            }
+            None => self.key("doc", note),
+        }
+    }
+}
+
+struct Ksy {
+    /// User types other than the root, in first-use order.
+    types: Vec<(String, Type)>,
+    /// Names of the structs already turned into user types.
+    done: Vec<String>,
+}
+
+impl Ksy {
+    fn struct_type(&mut self, schema: &Schema, root: bool) -> Type {
+        self.done.push(schema.name.clone());
+        let mut ty = Type {
+            doc: schema.doc.clone(),
+            ..Type::default()
+        };
+        // Offsets from the start of a nested struct need its position in the stream.
+        let from_start = |field: &FieldSchema| match &field.position {
+            Position::OffsetField { relative, .. } | Position::Seek { relative, .. } => {
+                !field.presence.skip && *relative == Relative::Start
+            }
+            Position::Sequential => false,
+        };
+        if !root && schema.fields.iter().any(from_start) {
+            capture_pos(&mut ty, "start");
+        }
+        // Bit offset modulo 8, while it is known; Kaitai aligns byte-sized reads to whole bytes.
+        let mut phase = Some(0);
+        for field in &schema.fields {
+            self.field(&mut ty, field, schema, root, &mut phase);
+        }
+        ty
+    }
+
+    fn field(
+        &mut self,
+        ty: &mut Type,
+        field: &FieldSchema,
+        schema: &Schema,
+        root: bool,
+        phase: &mut Option<usize>,
+    ) {
+        let presence = &field.presence;
+        if presence.skip {
+            return;
+        }
+        let mut conds = Vec::new();
+        if let Some(cond) = &presence.cond {
+            conds.push(expr(cond));
+        }
+        let version = schema.version.as_deref().map(expr).unwrap_or_default();
+        if let Some(since) = &presence.since {
+            conds.push(format!("{} >= {}", version, since));
+        }
+        if let Some(until) = &presence.until {
+            conds.push(format!("{} < {}", version, until));
+        }
+        let cond = match conds.len() {
+            0 => None,
+            1 => conds.pop(),
+            _ => Some(
+                conds
+                    .iter()
+                    .map(|cond| format!("({})", cond))
+                    .collect::<Vec<_>>()
+                    .join(" and "),
+            ),
+        };
+        let mut notes = Vec::new();
+        if field.wire_type.is_some() {
+            notes.push(format!("mapped to `{}`", field.rust_type));
+        }
+        if let Some(calc) = &field.calc {
+            notes.push(format!("computed as `{}`", calc));
+        }
+        if !field.doc.is_empty() {
+            notes.insert(0, field.doc.clone());
+        }
+
+        if field.position != Position::Sequential {
+            let (offset, relative) = match &field.position {
+                Position::OffsetField { field, relative } => (field.clone(), *relative),
+                Position::Seek {
+                    expr: seek,
+                    relative,
+                } => (expr(seek), *relative),
+                Position::Sequential => unreachable!(),
+            };
+            let base = match relative {
+                Relative::Start if root => None,
+                Relative::Start => Some("start_pos".to_string()),
+                Relative::Current if *phase == Some(0) => {
+                    capture_pos(ty, &field.name);
+                    Some(format!("{}_pos", field.name))
+                }
+                Relative::Current => {
+                    // Kaitai positions are whole bytes; fail when the field is read.
+                    let mut attr = Attr::new(field.name.as_str());
+                    attr.key("size", "0");
+                    attr.valid = Some("false".into());
+                    attr.note("unsupported: offset is relative to a position inside a byte");
+                    ty.instances.push(attr);
+                    return;
+                }
+            };
+            let pos = match base {
+                Some(base) if is_word(&offset) => format!("{} + {}", base, offset),
+                Some(base) => format!("{} + ({})", base, offset),
+                None => offset,
+            };
+            let mut attrs = Vec::new();
+            // Placed fields start on a byte boundary.
+            let mut inner = Some(0);
+            self.value(
+                &mut attrs,
+                &field.name,
+                field,
+                &mut inner,
+                &type_id(&schema.name),
+            );
+            for mut attr in attrs {
+                attr.key("pos", pos.as_str());
+                if let Some(cond) = &cond {
+                    attr.key("if", cond.as_str());
+                }
+                for note in &notes {
+                    attr.note(note);
+                }
+                ty.instances.push(attr);
+            }
+            return;
+        }
+
+        let prefix = type_id(&schema.name);
+        let mut attrs = Vec::new();
+        if field.pad_before > 0 {
+            pad(
+                &mut attrs,
+                format!("{}_pad_before", field.name),
+                field.pad_before,
+                phase,
+            );
+        }
+        let value_start = attrs.len();
+        self.value(&mut attrs, &field.name, field, phase, &prefix);
+        if let Some(align) = field.align {
+            self.field_align(&mut attrs, field, align, phase);
+        }
+        if field.pad_after > 0 {
+            pad(
+                &mut attrs,
+                format!("{}_pad_after", field.name),
+                field.pad_after,
+                phase,
+            );
+        }
+        if let Some(value) = attrs.get_mut(value_start) {
+            for note in &notes {
+                value.note(note);
+            }
+        }
+        for attr in &mut attrs {
+            if let Some(cond) = &cond {
+                attr.key("if", cond.as_str());
+            }
+        }
+        if cond.is_some() && !whole_bytes_field(field) {
+            *phase = None;
+        }
+        self.extra_instances(ty, field);
+        ty.seq.extend(attrs);
+    }
+
+    /// The attributes of a field's value, without field-level padding.
+    fn value(
+        &mut self,
+        attrs: &mut Vec<Attr>,
+        id: &str,
+        field: &FieldSchema,
+        phase: &mut Option<usize>,
+        prefix: &str,
+    ) {
+        let count = match &field.size {
+            SizeSource::Field(name) => Some(Count::Expr(name.clone())),
+            SizeSource::Func(name) => Some(Count::Func(name.clone())),
+            SizeSource::None => None,
+        };
+        self.layout(
+            attrs,
+            id,
+            &field.layout,
+            field.bits,
+            &field.aligns,
+            &field.sizes,
+            count,
+            phase,
+            prefix,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn layout(
+        &mut self,
+        attrs: &mut Vec<Attr>,
+        id: &str,
+        layout: &Layout,
+        bits: Option<usize>,
+        aligns: &[usize],
+        sizes: &[usize],
+        count: Option<Count>,
+        phase: &mut Option<usize>,
+        prefix: &str,
+    ) {
+        match layout {
+            Layout::Tuple(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let id = format!("{}_{}", id, index);
+                    self.layout(attrs, &id, item, bits, aligns, sizes, None, phase, prefix);
+                }
+            }
+            Layout::Array { len, item } => {
+                let count = Count::Expr(len.to_string());
+                self.repeated(attrs, id, item, bits, aligns, sizes, count, phase, prefix);
+            }
+            Layout::Seq { item } => {
+                let count = match (count, sizes.first().filter(|&&size| size != 0)) {
+                    (Some(count), _) => count,
+                    (None, Some(size)) => Count::Expr(size.to_string()),
+                    (None, None) => Count::Missing,
+                };
+                self.repeated(attrs, id, item, bits, aligns, sizes, count, phase, prefix);
+            }
+            Layout::Struct(schema) => {
+                let mut attr = Attr::new(id);
+                attr.key("type", self.user_type(schema));
+                attrs.push(attr);
+                advance(
+                    phase,
+                    layout.bit_width(bits, aligns),
+                    whole_bytes(layout, bits, aligns),
+                );
+            }
+            Layout::Opaque => {
+                let mut attr = Attr::new(id);
+                attr.key("size", "0");
+                attr.note("custom encoding, not described");
+                attrs.push(attr);
+                *phase = None;
+            }
+            scalar => {
+                let mut attr = Attr::new(id);
+                scalar_attr(&mut attr, scalar, bits, *phase == Some(0));
+                attrs.push(attr);
+                advance(phase, scalar.bit_width(bits, aligns), false);
+            }
+        }
+    }
+
+    /// A container of `count` items, each padded to the first level of `aligns`.
+    #[allow(clippy::too_many_arguments)]
+    fn repeated(
+        &mut self,
+        attrs: &mut Vec<Attr>,
+        id: &str,
+        item: &Layout,
+        bits: Option<usize>,
+        aligns: &[usize],
+        sizes: &[usize],
+        count: Count,
+        phase: &mut Option<usize>,
+        prefix: &str,
+    ) {
+        let count = match count {
+            Count::Expr(count) => count,
+            Count::Func(name) => {
+                let reason = format!("element count comes from `{}()`", name);
+                return unknown_count(attrs, id, &reason, phase);
+            }
+            Count::Missing => {
+                return unknown_count(attrs, id, "element count is not given", phase);
+            }
+        };
+        let inner_aligns = aligns.get(1..).unwrap_or_default();
+        let inner_sizes = sizes.get(1..).unwrap_or_default();
+        let align = aligns.first().copied().filter(|&align| align != 0);
+        let value_width = item.sized_bit_width(bits, inner_aligns, inner_sizes);
+        let width = item_width(item, bits, aligns, sizes);
+        let padding = value_width.zip(width).map(|(value, width)| width - value);
+        let whole = match align {
+            Some(align) if align.is_multiple_of(8) => true,
+            _ => whole_bytes(item, bits, inner_aligns),
+        };
+        // Every item starts at the same phase only if items are whole bytes wide.
+        let mut item_phase = if whole {
+            *phase
+        } else {
+            width.filter(|width| width.is_multiple_of(8)).and(*phase)
+        };
+
+        let mut attr = Attr::new(id);
+        let simple = matches!(
+            item,
+            Layout::Struct(_)
+                | Layout::Bool
+                | Layout::Int { .. }
+                | Layout::Float { .. }
+                | Layout::Fixed { .. }
+                | Layout::Reserved { .. }
+                | Layout::Flags { .. }
+        );
+        if simple && padding.unwrap_or(0) == 0 && (align.is_none() || padding.is_some()) {
+            match item {
+                Layout::Struct(schema) => attr.key("type", self.user_type(schema)),
+                scalar => scalar_attr(&mut attr, scalar, bits, item_phase == Some(0)),
+            }
+        } else {
+            // Items needing more than one attribute get a wrapper type.
+            let type_name = format!("{}_{}_item", prefix, id);
+            let index = self.types.len();
+            self.types.push((type_name.clone(), Type::default()));
+            let mut wrapper = Type::default();
+            self.layout(
+                &mut wrapper.seq,
+                "value",
+                item,
+                bits,
+                inner_aligns,
+                inner_sizes,
+                None,
+                &mut item_phase,
+                &type_name,
+            );
+            match (align, padding) {
+                (Some(_), Some(padding)) if padding > 0 => {
+                    pad(&mut wrapper.seq, "pad".into(), padding, &mut item_phase);
+                }
+                (Some(align), None) => {
+                    wrapper.doc = format!("padded to a multiple of {} bits", align)
+                }
+                _ => {}
+            }
+            self.types[index].1 = wrapper;
+            attr.key("type", type_name);
+        }
+        let total = count.parse::<usize>().ok().zip(width).map(|(n, w)| n * w);
+        attr.key("repeat", "expr");
+        attr.key("repeat-expr", expr(&count));
+        attrs.push(attr);
+        advance(phase, total, whole);
+    }
+
+    /// Padding after a field's value up to a multiple of `align` bits.
+    fn field_align(
+        &mut self,
+        attrs: &mut Vec<Attr>,
+        field: &FieldSchema,
+        align: usize,
+        phase: &mut Option<usize>,
+    ) {
+        let id = format!("{}_align", field.name);
+        if let Some(width) = field
+            .layout
+            .sized_bit_width(field.bits, &field.aligns, &field.sizes)
+            && field.size == SizeSource::None
+        {
+            let padding = width.next_multiple_of(align) - width;
+            if padding > 0 {
+                pad(attrs, id, padding, phase);
+            }
+            return;
+        }
+        // A repeated value of fixed-width items whose count is a field.
+        let item = match &field.layout {
+            Layout::Seq { item } => item_width(item, field.bits, &field.aligns, &field.sizes),
+            _ => None,
+        };
+        if let (SizeSource::Field(count), Some(item)) = (&field.size, item)
+            && align.is_multiple_of(8)
+            && item.is_multiple_of(8)
+        {
+            let (align, item) = (align / 8, item / 8);
+            let mut attr = Attr::new(id);
+            attr.key(
+                "size",
+                format!("({} - {} * {} % {}) % {}", align, count, item, align, align),
+            );
+            attrs.push(attr);
+            return;
+        }
+        if let Some(value) = attrs.iter_mut().rev().find(|attr| attr.id == field.name) {
+            value.note(&format!("padded to a multiple of {} bits", align));
+        }
+        *phase = None;
+    }
+
+    /// Value instances for flags and fixed-point fields.
+    fn extra_instances(&self, ty: &mut Type, field: &FieldSchema) {
+        match &field.layout {
+            Layout::Flags { flags, .. } => {
+                for (name, bits) in flags {
+                    let mut attr = Attr::new(format!("{}_{}", field.name, name.to_lowercase()));
+                    attr.key("value", format!("({} & {:#x}) != 0", field.name, bits));
+                    ty.instances.push(attr);
+                }
+            }
+            Layout::Fixed { frac, .. } => {
+                let mut attr = Attr::new(format!("{}_value", field.name));
+                attr.key("value", format!("{} / {}.0", field.name, 1u128 << frac));
+                ty.instances.push(attr);
+            }
+            _ => {}
+        }
+    }
+
+    /// The id of the user type for `schema`, generating it the first time.
+    fn user_type(&mut self, schema: &Schema) -> String {
+        let id = type_id(&schema.name);
+        if !self.done.contains(&schema.name) {
+            let index = self.types.len();
+            self.types.push((id.clone(), Type::default()));
+            self.types[index].1 = self.struct_type(schema, false);
+        }
+        id
+    }
+}
+
+/// Where a container's item count comes from.
+enum Count {
+    Expr(String),
+    Func(String),
+    Missing,
+}
+
+/// An attribute that stops parsing at a container Kaitai cannot count the items of.
+fn unknown_count(attrs: &mut Vec<Attr>, id: &str, reason: &str, phase: &mut Option<usize>) {
+    let mut attr = Attr::new(id);
+    attr.key("size", "0");
+    attr.valid = Some("false".into());
+    attr.note(&format!("unsupported: {}", reason));
+    attrs.push(attr);
+    *phase = None;
+}
+
+/// Captures the stream position at this point of the `seq` in a `{name}_pos`
+/// instance, which the `if` of a zero-size attribute evaluates there.
+fn capture_pos(ty: &mut Type, name: &str) {
+    let mut mark = Attr::new(format!("{}_mark", name));
+    mark.key("size", "0");
+    mark.key("if", format!("{}_pos >= 0", name));
+    ty.seq.push(mark);
+    let mut pos = Attr::new(format!("{}_pos", name));
+    pos.key("value", "_io.pos");
+    ty.instances.push(pos);
+}
+
+/// Whether a Kaitai expression is a single name or number.
+fn is_word(expr: &str) -> bool {
+    expr.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Sets the type of a single number, bool or reserved value.
+fn scalar_attr(attr: &mut Attr, layout: &Layout, bits: Option<usize>, aligned: bool) {
+    let byte_type = |width: usize| aligned && matches!(width, 8 | 16 | 32 | 64);
+    match layout {
+        Layout::Bool => attr.key("type", "b1"),
+        Layout::Int { signed, bits: full }
+        | Layout::Fixed {
+            signed, bits: full, ..
+        } => {
+            let width = bits.unwrap_or(*full);
+            if byte_type(width) {
+                let sign = if *signed { 's' } else { 'u' };
+                attr.key("type", format!("{}{}", sign, width / 8));
+            } else {
+                attr.key("type", format!("b{}", width));
+                if *signed {
+                    attr.note(&format!("signed, two's complement in {} bits", width));
+                }
+            }
+            if let Layout::Fixed { frac, .. } = layout {
+                attr.note(&format!("fixed-point with {} fractional bits", frac));
+            }
+        }
+        Layout::Float { bits, kind } => match (bits, kind) {
+            (32 | 64, FloatKind::Ieee) if aligned => attr.key("type", format!("f{}", bits / 8)),
+            _ => {
+                attr.key(
+                    "type",
+                    if byte_type(*bits) {
+                        format!("u{}", bits / 8)
+                    } else {
+                        format!("b{}", bits)
+                    },
+                );
+                attr.note(&format!("{} bits", kind.name(*bits)));
+            }
+        },
+        Layout::Reserved { bits, strict } => {
+            if *bits > 64 {
+                reserved_bits(attr, *bits, aligned);
+            } else {
+                attr.key(
+                    "type",
+                    if byte_type(*bits) {
+                        format!("u{}", bits / 8)
+                    } else {
+                        format!("b{}", bits)
+                    },
+                );
+                if *strict {
+                    attr.key("valid", "0");
+                }
+            }
+        }
+        Layout::Flags { repr, .. } => scalar_attr(attr, repr, bits, aligned),
+        _ => {}
+    }
+}
+
+/// Types a run of bits too long for one integer.
+fn reserved_bits(attr: &mut Attr, bits: usize, aligned: bool) {
+    if aligned && bits.is_multiple_of(8) {
+        attr.key("size", (bits / 8).to_string());
+    } else {
+        attr.key("type", "b1");
+        attr.key("repeat", "expr");
+        attr.key("repeat-expr", bits.to_string());
+    }
+}
+
+/// Pushes a padding attribute of `bits` zero bits.
+fn pad(attrs: &mut Vec<Attr>, id: String, bits: usize, phase: &mut Option<usize>) {
+    let mut attr = Attr::new(id);
+    if bits <= 64 {
+        let aligned = *phase == Some(0) && matches!(bits, 8 | 16 | 32 | 64);
+        attr.key(
+            "type",
+            if aligned {
+                format!("u{}", bits / 8)
+            } else {
+                format!("b{}", bits)
+            },
+        );
+    } else {
+        reserved_bits(&mut attr, bits, *phase == Some(0));
+    }
+    attrs.push(attr);
+    advance(phase, Some(bits), false);
+}
+
+/// Moves the bit phase past a value of `width` bits, or of unknown width that is
+/// `whole` bytes.
+pub(crate) fn advance(phase: &mut Option<usize>, width: Option<usize>, whole: bool) {
+    *phase = match width {
+        Some(width) => phase.map(|phase| (phase + width) % 8),
+        None if whole => *phase,
+        None => None,
+    };
+}
+
+/// Whether every value of the layout is a whole number of bytes wide.
+pub(crate) fn whole_bytes(layout: &Layout, bits: Option<usize>, aligns: &[usize]) -> bool {
+    if let Some(width) = layout.bit_width(bits, aligns) {
+        return width.is_multiple_of(8);
+    }
+    match layout {
+        Layout::Array { item, .. } | Layout::Seq { item } => {
+            match aligns.first().copied().filter(|&align| align != 0) {
+                Some(align) if align.is_multiple_of(8) => true,
+                _ => whole_bytes(item, bits, aligns.get(1..).unwrap_or_default()),
+            }
+        }
+        Layout::Tuple(items) => items.iter().all(|item| whole_bytes(item, bits, aligns)),
+        Layout::Struct(schema) => schema.fields.iter().all(whole_bytes_field),
+        _ => false,
+    }
+}
+
+pub(crate) fn whole_bytes_field(field: &FieldSchema) -> bool {
+    if field.presence.skip || field.position != Position::Sequential {
+        return true;
+    }
+    let value = match field.align {
+        Some(align) if align.is_multiple_of(8) => true,
+        _ => whole_bytes(&field.layout, field.bits, &field.aligns),
+    };
+    value && field.pad_before.is_multiple_of(8) && field.pad_after.is_multiple_of(8)
+}
+
+fn push_type_body(out: &mut String, ty: &Type, indent: usize) {
+    let pad = " ".repeat(indent);
+    if !ty.doc.is_empty() {
+        let _ = writeln!(out, "{}doc: {}", pad, scalar(&ty.doc));
+    }
+    if !ty.seq.is_empty() {
+        let _ = writeln!(out, "{}seq:", pad);
+        for attr in &ty.seq {
+            let _ = writeln!(out, "{}  - id: {}", pad, attr.id);
+            push_keys(out, attr, &pad);
+        }
+    }
+    if !ty.instances.is_empty() {
+        let _ = writeln!(out, "{}instances:", pad);
+        for attr in &ty.instances {
+            let _ = writeln!(out, "{}  {}:", pad, attr.id);
+            push_keys(out, attr, &pad);
+        }
+    }
+}
+
+/// Writes an attribute's keys and `valid` check, indented under its id.
+fn push_keys(out: &mut String, attr: &Attr, pad: &str) {
+    for (key, value) in &attr.keys {
+        let _ = writeln!(out, "{}    {}: {}", pad, key, scalar(value));
+    }
+    if let Some(valid) = &attr.valid {
+        let _ = writeln!(
+            out,
+            "{}    valid:\n{}      expr: {}",
+            pad,
+            pad,
+            scalar(valid)
+        );
+    }
+}
+
+/// A YAML scalar, double-quoted unless it is plain text.
+fn scalar(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value.starts_with([' ', '-'])
+        && !value.ends_with(' ')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ' ' | '.' | '-'));
+    if plain {
+        return value.into();
+    }
+    let mut out = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `Packet` → `packet`, `GameSave` → `game_save`, `HTTPHeader` → `http_header`.
+pub(crate) fn type_id(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut id = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let after_lower =
+                i > 0 && (chars[i - 1].is_ascii_lowercase() || chars[i - 1].is_ascii_digit());
+            let ends_acronym = i > 0
+                && chars[i - 1].is_ascii_uppercase()
+                && chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            if after_lower || ends_acronym {
+                id.push('_');
+            }
+            id.push(c.to_ascii_lowercase());
+        } else {
+            id.push(c);
+        }
+    }
+    id
+}
+
+/// Rewrites a Rust expression over the struct's fields as a Kaitai expression.
+fn expr(rust: &str) -> String {
+    let ident_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::new();
+    let mut rest = rust;
+    while let Some(c) = rest.chars().next() {
+        let after_ident = out.ends_with(ident_char);
+        if let Some(after) = rest.strip_prefix("self.").filter(|_| !after_ident) {
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("&&") {
+            out.push_str("and");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("||") {
+            out.push_str("or");
+            rest = after;
+        } else if c == '!' && !rest.starts_with("!=") {
+            out.push_str("not ");
+            rest = rest[1..].trim_start();
+        } else if let Some(after) = rest.strip_prefix(".len()") {
+            out.push_str(".size");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(" as ") {
+            // Casts only change Rust types.
+            rest = after.trim_start_matches(ident_char);
+        } else if c == '.'
+            && rest[1..].starts_with(|c: char| c.is_ascii_digit())
+            && out
+                .rsplit(|c: char| !ident_char(c))
+                .next()
+                .is_some_and(|word| word.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+        {
+            // Tuple members are flattened into `name_index` attributes.
+            out.push('_');
+            rest = &rest[1..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+fn endian(bit_order: &str) -> &'static str {
+    match bit_order {
+        "Msb0" => "be",
+        _ => "le",
+    }
+}
@@ -1,32 +1,199 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod c;
+pub mod docgen;
+pub mod dump;
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
+mod error;
 pub mod field;
-pub use bitvec::prelude::*;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod kaitai;
+pub mod schema;
+pub mod trace;
+pub mod wireshark;
 pub use bitvec::field::BitField;
+pub use bitvec::prelude::*;
+pub use error::Error;
+#[cfg(feature = "fixed")]
+pub use field::fixed::Fixed;
+#[cfg(feature = "reserved")]
+pub use field::reserved::Reserved;
+#[cfg(feature = "half")]
+pub use half::{bf16, f16};
+pub use schema::{FloatKind, Layout, Schema};
 pub use shua_struct_macro::BinaryStruct;
+pub use trace::Span;
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::format;
+    pub use alloc::rc::Rc;
+    pub use alloc::vec::Vec;
+    pub use core::any::Any;
+
+    use alloc::string::String;
+    use bitvec::order::BitOrder;
+    use core::fmt::Debug;
+
+    /// Calls `f` with `value`, letting the closure's argument type be inferred.
+    pub fn apply<W, T>(value: W, f: impl FnOnce(W) -> T) -> T {
+        f(value)
+    }
+
+    /// Shows a field value in a trace if its type implements `Debug`.
+    ///
+    /// `(&Show(&value)).show()` picks [`ShowDebug`] when it applies and falls back to
+    /// [`ShowNone`] otherwise; this only works where the value's type is concrete.
+    pub struct Show<'a, T>(pub &'a T);
+
+    pub trait ShowDebug {
+        fn show(&self) -> Option<String>;
+    }
+
+    impl<T: Debug> ShowDebug for Show<'_, T> {
+        fn show(&self) -> Option<String> {
+            Some(format!("{:?}", self.0))
+        }
+    }
+
+    pub trait ShowNone {
+        fn show(&self) -> Option<String>;
+    }
+
+    impl<T> ShowNone for &Show<'_, T> {
+        fn show(&self) -> Option<String> {
+            None
+        }
+    }
+
+    /// Sets the value of the most recently traced field if it is a leaf.
+    pub fn show_last<O: BitOrder>(span: &mut crate::Span<O>, value: Option<String>) {
+        if let (Some(last), Some(value)) = (span.children.last_mut(), value)
+            && last.children.is_empty()
+        {
+            last.value = value;
+        }
+    }
+}
 
-#[derive(Debug, Default)]
+/// Layout options for one field, described per nesting level.
+///
+/// Level 0 is the field itself (e.g. the element count and element alignment
+/// of a `Vec`), level 1 its elements, and so on. Options are never mutated;
+/// containers hand [`Options::child`] to their elements instead.
+#[derive(Default, Clone)]
 pub struct Options {
-    pub size: usize,
-    pub align: usize,
-    pub sub_align: std::cell::Cell<u8>,
+    /// Element count per level; `0` entries are placeholders for levels without a count.
+    pub sizes: Vec<usize>,
+    /// Element count of level 0 read from the data by `size_field` or `size_func`.
+    /// It overrides `sizes[0]` and, unlike a placeholder, may be `0`.
+    pub count: Option<usize>,
+    /// Alignment in bits applied after each element per level; `0` disables it.
+    pub aligns: Vec<usize>,
+    /// Context value handed from a parent struct, shared by every nesting level.
+    pub ctx: Option<Rc<dyn Any>>,
+    /// Wire width in bits of integer-backed leaf values, shared by every nesting level.
+    pub bits: Option<usize>,
 }
 
 impl Options {
-    pub fn get_align(&self) -> Option<usize> {
-        let n = self.sub_align.get();
-        if n == 0 {
+    /// Element count of the current level, if one was given; `0` placeholders are none.
+    pub fn size(&self) -> Option<usize> {
+        self.count
+            .or_else(|| self.sizes.first().copied().filter(|&size| size != 0))
+    }
+
+    /// Element alignment of the current level, if any.
+    pub fn align(&self) -> Option<usize> {
+        self.aligns.first().copied().filter(|&align| align != 0)
+    }
+
+    /// Options for the next nesting level, or `None` when nothing is left to apply.
+    pub fn child(&self) -> Option<Options> {
+        if self.sizes.len() <= 1
+            && self.aligns.len() <= 1
+            && self.ctx.is_none()
+            && self.bits.is_none()
+        {
             return None;
         }
-        let new = n - 1;
-        self.sub_align.set(new);
-        if new == 0 { Some(self.align) } else { None }
+        Some(Options {
+            sizes: self.sizes.get(1..).unwrap_or_default().to_vec(),
+            count: None,
+            aligns: self.aligns.get(1..).unwrap_or_default().to_vec(),
+            ctx: self.ctx.clone(),
+            bits: self.bits,
+        })
+    }
+
+    /// Attaches a context value for a struct declared with `#[binary_struct(ctx = C)]`.
+    pub fn with_ctx<C: Any>(mut self, ctx: C) -> Self {
+        self.ctx = Some(Rc::new(ctx));
+        self
+    }
+
+    /// The attached context value, if it has type `C`.
+    pub fn ctx<C: Any>(&self) -> Option<&C> {
+        self.ctx.as_deref().and_then(|ctx| ctx.downcast_ref())
+    }
+}
+
+impl fmt::Debug for Options {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Options")
+            .field("sizes", &self.sizes)
+            .field("count", &self.count)
+            .field("aligns", &self.aligns)
+            .field("ctx", &self.ctx.as_ref().map(|_| ".."))
+            .field("bits", &self.bits)
+            .finish()
     }
 }
 
 pub trait BinaryField<O: bitvec::prelude::BitOrder>: Sized {
+    /// Alignment in bits this type has as a C struct member, used by
+    /// `#[binary_struct(repr_c)]`; see [`c`].
+    ///
+    /// The default starts values on a byte boundary. One-bit `bool`s and `Reserved`
+    /// bits use `1`, as they pack like C bitfields.
+    const C_ALIGN: usize = 8;
+
     fn parse(
         bits: &bitvec::prelude::BitSlice<u8, O>,
         opts: &Option<Options>,
-    ) -> Result<(Self, usize), String>;
+    ) -> Result<(Self, usize), Error>;
+
+    fn build(&self, opts: &Option<Options>) -> Result<bitvec::prelude::BitVec<u8, O>, Error>;
+
+    /// Like [`parse`](Self::parse), additionally recording the spans of nested values
+    /// as children of `span`, the span of this value; see [`Span::trace`].
+    ///
+    /// The default records nothing; leaf values, containers and derived structs override it.
+    fn parse_traced(
+        bits: &bitvec::prelude::BitSlice<u8, O>,
+        opts: &Option<Options>,
+        span: &mut Span<O>,
+    ) -> Result<(Self, usize), Error> {
+        let _ = span;
+        Self::parse(bits, opts)
+    }
 
-    fn build(&self, opts: &Option<Options>) -> Result<bitvec::prelude::BitVec<u8, O>, String>;
+    /// Describes the wire shape of this type for [`Schema`] reflection.
+    ///
+    /// Self-referential types (e.g. a struct holding `Vec<Self>`) must override
+    /// this without recursing, or their layout never terminates.
+    fn layout() -> Layout {
+        Layout::Opaque
+    }
 }
@@ -0,0 +1,296 @@
+//! Runtime description of derived types.
+//!
+//! Every `#[derive(BinaryStruct)]` type gets an inherent `schema()` function
+//! returning a [`Schema`], and every [`BinaryField`](crate::BinaryField)
+//! reports its wire shape through [`BinaryField::layout`](crate::BinaryField::layout).
+//! The schema is plain data, so tools can walk it to generate documentation,
+//! dissectors or validators.
+//!
+//! With the `serde` feature the schema types implement `Serialize` and
+//! `Deserialize`, so a schema can also be written by hand in JSON or TOML and
+//! interpreted with the `dynamic` module. Every key besides a struct's `name`
+//! and `fields` and a field's `name` and `layout` may be left out.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Layout of a derived struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schema {
+    /// Name of the Rust type.
+    pub name: String,
+    /// Doc comment of the Rust type, one line per `///` line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub doc: String,
+    /// Name of the bit order, `"Lsb0"` (little-endian values) or `"Msb0"` (big-endian values).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bit_order: String,
+    /// The `version` expression `since`/`until` compare against, e.g. `self.version`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: Option<String>,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl Schema {
+    /// Total width in bits, if it does not depend on the data.
+    pub fn bit_width(&self) -> Option<usize> {
+        self.fields.iter().map(FieldSchema::bit_width).sum()
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// One field of a [`Schema`], with the options given in its `#[binary_field(...)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldSchema {
+    pub name: String,
+    /// Doc comment of the field, one line per `///` line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub doc: String,
+    /// The field's Rust type as written.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rust_type: String,
+    /// The `wire` type the value is mapped through, if any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub wire_type: Option<String>,
+    /// Layout of the type on the wire; [`Layout::Opaque`] for `parse_with`/`build_with` fields.
+    pub layout: Layout,
+    /// `bits` width override for integer-backed values.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bits: Option<usize>,
+    /// Alignment in bits of the whole field (`align` without `sub_align`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub align: Option<usize>,
+    /// Element alignment per nesting level, including a `sub_align`ed `align`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub aligns: Vec<usize>,
+    /// Element count per nesting level from `sizes`; level 0 may be overridden by `size`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sizes: Vec<usize>,
+    /// Where the element count of level 0 comes from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub size: SizeSource,
+    /// Zero bits before the field.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pad_before: usize,
+    /// Zero bits after the field.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pad_after: usize,
+    /// Conditions under which the field is on the wire.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub presence: Presence,
+    /// Where the field is placed, for `offset_field`/`seek` fields.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub position: Position,
+    /// Expression the field is built from, for `calc` fields.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub calc: Option<String>,
+}
+
+impl FieldSchema {
+    /// Width in bits including padding and alignment, if it does not depend on the data.
+    ///
+    /// Skipped fields are 0 bits wide; conditional and pointer fields are never fixed.
+    pub fn bit_width(&self) -> Option<usize> {
+        if self.presence.skip {
+            return Some(0);
+        }
+        if self.presence.is_conditional() || self.position != Position::Sequential {
+            return None;
+        }
+        if self.size != SizeSource::None {
+            return None;
+        }
+        let width = self
+            .layout
+            .sized_bit_width(self.bits, &self.aligns, &self.sizes)?;
+        Some(self.pad_before + align_up(width, self.align) + self.pad_after)
+    }
+}
+
+/// Source of a field's element count.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SizeSource {
+    #[default]
+    None,
+    /// `size_field = name`: an earlier field.
+    Field(String),
+    /// `size_func = name`: a method on the struct.
+    Func(String),
+}
+
+/// When a field is on the wire.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Presence {
+    /// `skip`: never on the wire.
+    pub skip: bool,
+    /// `cond` expression.
+    pub cond: Option<String>,
+    /// `since` version, inclusive.
+    pub since: Option<String>,
+    /// `until` version, exclusive.
+    pub until: Option<String>,
+}
+
+impl Presence {
+    /// Whether the field is only present for some values.
+    pub fn is_conditional(&self) -> bool {
+        self.cond.is_some() || self.since.is_some() || self.until.is_some()
+    }
+}
+
+/// Where a field is placed in its struct.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Position {
+    /// Right after the previous field.
+    #[default]
+    Sequential,
+    /// At the byte offset stored in `field`.
+    OffsetField { field: String, relative: Relative },
+    /// At the byte offset computed by `expr`.
+    Seek { expr: String, relative: Relative },
+}
+
+/// Base of a pointer field's byte offset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Relative {
+    #[default]
+    Start,
+    Current,
+}
+
+/// Wire shape of a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Layout {
+    Bool,
+    /// An integer; `bits` is its full width before any `bits` option.
+    Int {
+        signed: bool,
+        bits: usize,
+    },
+    Float {
+        bits: usize,
+        #[cfg_attr(feature = "serde", serde(default))]
+        kind: FloatKind,
+    },
+    /// A fixed-point number stored as its raw integer.
+    Fixed {
+        signed: bool,
+        bits: usize,
+        frac: u32,
+    },
+    Reserved {
+        bits: usize,
+        strict: bool,
+    },
+    /// A bitflags set stored as its raw integer, with its named flags.
+    Flags {
+        repr: Box<Layout>,
+        flags: Vec<(String, u64)>,
+    },
+    /// `[T; N]`.
+    Array {
+        len: usize,
+        item: Box<Layout>,
+    },
+    /// A collection whose element count comes from the `size` option; maps hold key/value tuples.
+    Seq {
+        item: Box<Layout>,
+    },
+    Tuple(Vec<Layout>),
+    Struct(Schema),
+    /// A type that does not describe itself.
+    Opaque,
+}
+
+/// Encoding of a [`Layout::Float`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FloatKind {
+    /// IEEE 754 binary16, binary32 or binary64 (`f16`, `f32`, `f64`).
+    #[default]
+    Ieee,
+    /// bfloat16, the upper half of a binary32 (`bf16`).
+    Bfloat,
+}
+
+impl FloatKind {
+    /// Name of the `bits`-bit encoding, e.g. `IEEE binary16` or `bfloat16`.
+    pub fn name(self, bits: usize) -> String {
+        match self {
+            FloatKind::Ieee => format!("IEEE binary{}", bits),
+            FloatKind::Bfloat => format!("bfloat{}", bits),
+        }
+    }
+}
+
+impl Layout {
+    /// Width in bits given a `bits` option and per-level element alignments, if fixed.
+    pub fn bit_width(&self, bits: Option<usize>, aligns: &[usize]) -> Option<usize> {
+        self.sized_bit_width(bits, aligns, &[])
+    }
+
+    /// [`Layout::bit_width`] with per-level element counts from a `sizes` option,
+    /// which fix the width of sequences.
+    pub(crate) fn sized_bit_width(
+        &self,
+        bits: Option<usize>,
+        aligns: &[usize],
+        sizes: &[usize],
+    ) -> Option<usize> {
+        match self {
+            Layout::Bool => Some(1),
+            Layout::Int { bits: full, .. } | Layout::Fixed { bits: full, .. } => {
+                Some(bits.unwrap_or(*full))
+            }
+            Layout::Float { bits, .. } | Layout::Reserved { bits, .. } => Some(*bits),
+            Layout::Flags { repr, .. } => repr.sized_bit_width(bits, aligns, sizes),
+            Layout::Array { len, item } => Some(len * item_width(item, bits, aligns, sizes)?),
+            Layout::Seq { item } => {
+                let size = sizes.first().copied().filter(|&size| size != 0)?;
+                Some(size * item_width(item, bits, aligns, sizes)?)
+            }
+            Layout::Tuple(items) => items
+                .iter()
+                .map(|item| item.sized_bit_width(bits, aligns, sizes))
+                .sum(),
+            Layout::Struct(schema) => schema.bit_width(),
+            Layout::Opaque => None,
+        }
+    }
+}
+
+/// Width of one element of a container at the first level of `aligns` and `sizes`.
+pub(crate) fn item_width(
+    item: &Layout,
+    bits: Option<usize>,
+    aligns: &[usize],
+    sizes: &[usize],
+) -> Option<usize> {
+    let align = aligns.first().copied().filter(|&align| align != 0);
+    let width = item.sized_bit_width(
+        bits,
+        aligns.get(1..).unwrap_or_default(),
+        sizes.get(1..).unwrap_or_default(),
+    )?;
+    Some(align_up(width, align))
+}
+
+fn align_up(width: usize, align: Option<usize>) -> usize {
+    match align {
+        Some(align) => width.next_multiple_of(align),
+        None => width,
+    }
+}
@@ -0,0 +1,171 @@
+//! Field spans recorded while parsing, for debugging corrupt inputs.
+//!
+//! [`Span::trace`] parses a value like [`BinaryField::parse`] and additionally
+//! returns a tree of [`Span`]s: one per struct field, container element and
+//! tuple member, each with its path, bit range, raw bits and (for leaf values)
+//! the parsed value.
+
+use crate::{BinaryField, Error, Options};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use bitvec::prelude::*;
+
+/// The bits one parsed value occupies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span<O: BitOrder = Lsb0> {
+    /// Path from the outermost value, e.g. `players[0].skills[2].multiplier`; empty for the outermost value.
+    pub path: String,
+    /// Offset of the first bit from the start of the outermost value.
+    pub start_bit: usize,
+    pub len_bits: usize,
+    /// The bits of the value as they appear in the input.
+    pub raw: BitVec<u8, O>,
+    /// The parsed value as `Debug` shows it; empty for containers, structs and
+    /// values that cannot be shown.
+    pub value: String,
+    /// Whether parsing the value failed; its children show how far it got.
+    pub failed: bool,
+    pub children: Vec<Span<O>>,
+}
+
+impl<O: BitOrder> Span<O> {
+    fn new(path: String, start_bit: usize) -> Self {
+        Span {
+            path,
+            start_bit,
+            len_bits: 0,
+            raw: BitVec::new(),
+            value: String::new(),
+            failed: false,
+            children: Vec::new(),
+        }
+    }
+
+    /// Parses a `T` from the start of `bits`, recording the spans of everything in it.
+    ///
+    /// The spans are returned even if parsing fails, up to the failing value.
+    pub fn trace<T: BinaryField<O>>(
+        bits: &BitSlice<u8, O>,
+        opts: &Option<Options>,
+    ) -> (Result<T, Error>, Span<O>) {
+        let mut span = Span::new(String::new(), 0);
+        let result = span.record(bits, |span| T::parse_traced(bits, opts, span));
+        (result.map(|(value, _)| value), span)
+    }
+
+    /// Parses struct field `name`, which starts `offset` bits into this span, into a child span.
+    ///
+    /// `bits` starts at the field; `parse` records the field's own children.
+    pub fn trace_field<T, E>(
+        &mut self,
+        name: &str,
+        offset: usize,
+        bits: &BitSlice<u8, O>,
+        parse: impl FnOnce(&mut Span<O>) -> Result<(T, usize), E>,
+    ) -> Result<(T, usize), E> {
+        let path = if self.path.is_empty() {
+            String::from(name)
+        } else {
+            format!("{}.{}", self.path, name)
+        };
+        self.trace_child(path, offset, bits, parse)
+    }
+
+    /// Parses element `index`, which starts `offset` bits into this span, into a child span.
+    pub fn trace_index<T, E>(
+        &mut self,
+        index: usize,
+        offset: usize,
+        bits: &BitSlice<u8, O>,
+        parse: impl FnOnce(&mut Span<O>) -> Result<(T, usize), E>,
+    ) -> Result<(T, usize), E> {
+        let path = format!("{}[{}]", self.path, index);
+        self.trace_child(path, offset, bits, parse)
+    }
+
+    fn trace_child<T, E>(
+        &mut self,
+        path: String,
+        offset: usize,
+        bits: &BitSlice<u8, O>,
+        parse: impl FnOnce(&mut Span<O>) -> Result<(T, usize), E>,
+    ) -> Result<(T, usize), E> {
+        let mut child = Span::new(path, self.start_bit + offset);
+        let result = child.record(bits, parse);
+        self.children.push(child);
+        result
+    }
+
+    fn record<T, E>(
+        &mut self,
+        bits: &BitSlice<u8, O>,
+        parse: impl FnOnce(&mut Span<O>) -> Result<(T, usize), E>,
+    ) -> Result<(T, usize), E> {
+        let result = parse(self);
+        match &result {
+            Ok((_, len)) => {
+                self.len_bits = *len;
+                self.raw = bits.get(..*len).unwrap_or(bits).to_bitvec();
+            }
+            Err(_) => self.failed = true,
+        }
+        result
+    }
+
+    /// The span with exactly this path, searching this span and everything below it.
+    pub fn get(&self, path: &str) -> Option<&Span<O>> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.get(path))
+    }
+
+    /// The innermost span containing the absolute bit offset `bit`.
+    pub fn at_bit(&self, bit: usize) -> Option<&Span<O>> {
+        if !(self.start_bit..self.start_bit + self.len_bits).contains(&bit) {
+            return None;
+        }
+        Some(
+            self.children
+                .iter()
+                .find_map(|child| child.at_bit(bit))
+                .unwrap_or(self),
+        )
+    }
+
+    /// This span and all spans below it, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &Span<O>> {
+        let mut stack = Vec::from([self]);
+        core::iter::from_fn(move || {
+            let span = stack.pop()?;
+            stack.extend(span.children.iter().rev());
+            Some(span)
+        })
+    }
+}
+
+/// Parses with `T::parse_traced` if there is a span to record into, else with `T::parse`.
+#[cfg(feature = "array")]
+pub(crate) fn parse_maybe_traced<T: BinaryField<O>, O: BitOrder>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    span: Option<&mut Span<O>>,
+) -> Result<(T, usize), Error> {
+    match span {
+        Some(span) => T::parse_traced(bits, opts, span),
+        None => T::parse(bits, opts),
+    }
+}
+
+/// `parse_traced` for leaf values: parses and shows the value in `span`.
+#[cfg(any(feature = "bool", feature = "int", feature = "float"))]
+pub(crate) fn parse_leaf<T: BinaryField<O> + core::fmt::Debug, O: BitOrder>(
+    bits: &BitSlice<u8, O>,
+    opts: &Option<Options>,
+    span: &mut Span<O>,
+) -> Result<(T, usize), Error> {
+    let (value, len) = T::parse(bits, opts)?;
+    span.value = format!("{:?}", value);
+    Ok((value, len))
+}
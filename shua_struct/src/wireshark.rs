@@ -0,0 +1,899 @@
+//! Wireshark Lua dissectors for derived types, generated from their [`Schema`].
+//!
+//! The dissector walks the packet bit by bit the way the derive parses it. Every
+//! value gets a `ProtoField`, filterable as `packet.field` (or `packet.type.field`
+//! inside nested structs); where a value's bit position within its bytes is known
+//! up front, the field carries a bitmask so Wireshark shows the covered bits.
+//! Nested structs become subtrees, containers loop over their element count
+//! (a literal, `sizes` or `size_field`), and `cond`, `since`/`until` and
+//! `offset_field`/`seek` are evaluated on the values read so far.
+//!
+//! What needs compiled code (`size_func` counts, `parse_with` fields) stops the
+//! dissection with a Lua error at that point. The script uses Lua 5.3 integer
+//! and bitwise operators, as shipped with Wireshark 4.4 and later, and registers
+//! the protocol for UDP "Decode As".
+//!
+//! ```ignore
+//! std::fs::write("packet.lua", shua_struct::wireshark::lua(&Packet::schema()))?;
+//! // wireshark -X lua_script:packet.lua
+//! ```
+
+use crate::kaitai::{advance, type_id, whole_bytes, whole_bytes_field};
+use crate::schema::{
+    FieldSchema, FloatKind, Layout, Position, Relative, Schema, SizeSource, item_width,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+const LUA_KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+const INT_SUFFIXES: &[&str] = &[
+    "u128", "i128", "usize", "isize", "u64", "i64", "u32", "i32", "u16", "i16", "u8", "i8",
+];
+
+/// Renders `schema` as a Wireshark Lua dissector, with every struct nested in it as a subtree.
+pub fn lua(schema: &Schema) -> String {
+    let proto = type_id(&schema.name);
+    let mut lua = Lua {
+        proto: proto.clone(),
+        root: schema.name.clone(),
+        le: schema.bit_order != "Msb0",
+        fields: Vec::new(),
+        functions: Vec::new(),
+        done: Vec::new(),
+    };
+    let root = lua.struct_fn(schema, Some(0));
+
+    let mut out = format!(
+        "-- Wireshark dissector for `{}`, generated by shua_struct.\n\n",
+        schema.name
+    );
+    let _ = writeln!(
+        out,
+        "local proto = Proto({:?}, {:?})\n",
+        proto,
+        description(schema)
+    );
+    out.push_str("local hf = {}\n");
+    for (var, def) in &lua.fields {
+        let _ = writeln!(out, "{} = {}", member("hf", var), def);
+    }
+    out.push_str("proto.fields = {\n");
+    for (var, _) in &lua.fields {
+        let _ = writeln!(out, "    {},", member("hf", var));
+    }
+    out.push_str("}\n\n");
+    out.push_str(if lua.le { READ_LE } else { READ_BE });
+    out.push_str(HELPERS);
+    // Declared up front, so dissectors may call ones defined after them.
+    let names: Vec<&str> = lua.done.iter().map(|(_, _, name)| name.as_str()).collect();
+    let _ = write!(out, "\nlocal {}\n", names.join(", "));
+    for function in &lua.functions {
+        out.push('\n');
+        out.push_str(function);
+    }
+    let _ = write!(
+        out,
+        "
+function proto.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = proto.name
+    local bit = {}(tvb, tree:add(proto, tvb()), 0)
+    return (bit + 7) // 8
+end
+
+-- Pick the port under Decode As, or register one: DissectorTable.get(\"udp.port\"):add(5000, proto)
+DissectorTable.get(\"udp.port\"):add_for_decode_as(proto)
+",
+        root
+    );
+    out
+}
+
+const READ_LE: &str = "\
+-- Reads `width` bits at bit offset `bit` as an unsigned integer, least significant bits first.
+local function read(tvb, bit, width)
+    local value = 0
+    local shift = 0
+    while shift < width do
+        local at = bit % 8
+        local take = math.min(8 - at, width - shift)
+        local byte = tvb(bit // 8, 1):uint()
+        value = value | ((byte >> at) & ((1 << take) - 1)) << shift
+        bit = bit + take
+        shift = shift + take
+    end
+    return value
+end
+";
+
+const READ_BE: &str = "\
+-- Reads `width` bits at bit offset `bit` as an unsigned integer, most significant bits first.
+local function read(tvb, bit, width)
+    local value = 0
+    local remaining = width
+    while remaining > 0 do
+        local at = bit % 8
+        local take = math.min(8 - at, remaining)
+        local byte = tvb(bit // 8, 1):uint()
+        value = value << take | (byte >> (8 - at - take)) & ((1 << take) - 1)
+        bit = bit + take
+        remaining = remaining - take
+    end
+    return value
+end
+";
+
+const HELPERS: &str = "
+local function signed(value, width)
+    if width < 64 and value >= 1 << (width - 1) then
+        return value - (1 << width)
+    end
+    return value
+end
+
+local function float(raw, width)
+    if width == 16 then
+        local sign = raw >> 15 == 1 and -1 or 1
+        local exponent = raw >> 10 & 0x1f
+        local fraction = raw & 0x3ff
+        if exponent == 0 then
+            return sign * fraction * 2^-24
+        elseif exponent == 31 then
+            return fraction == 0 and sign * math.huge or 0 / 0
+        end
+        return sign * (1 + fraction / 1024) * 2^(exponent - 15)
+    end
+    if width == 32 then
+        return (string.unpack(\"<f\", string.pack(\"<I4\", raw)))
+    end
+    return (string.unpack(\"<d\", string.pack(\"<i8\", raw)))
+end
+
+-- The bytes covering `width` bits at bit offset `bit`.
+local function span(tvb, bit, width)
+    return tvb(bit // 8, (bit % 8 + width + 7) // 8)
+end
+
+-- Rounds `bit` up to a multiple of `to` bits past `first`.
+local function align(bit, first, to)
+    return first + (bit - first + to - 1) // to * to
+end
+";
+
+/// The protocol description: the first doc line, or the type name.
+fn description(schema: &Schema) -> String {
+    schema
+        .doc
+        .lines()
+        .next()
+        .filter(|line| !line.is_empty())
+        .unwrap_or(&schema.name)
+        .to_string()
+}
+
+/// Lua source being written line by line.
+#[derive(Default)]
+struct Code {
+    out: String,
+    depth: usize,
+}
+
+impl Code {
+    fn line(&mut self, text: impl AsRef<str>) {
+        for _ in 0..self.depth {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    /// Writes `text` and indents what follows.
+    fn open(&mut self, text: impl AsRef<str>) {
+        self.line(text);
+        self.depth += 1;
+    }
+
+    fn close(&mut self, text: impl AsRef<str>) {
+        self.depth -= 1;
+        self.line(text);
+    }
+}
+
+/// How a value of known bit phase is added: the bytes it covers and its bit shift in them.
+struct Placement {
+    bytes: usize,
+    shift: usize,
+    /// Whether the value covers only part of its bytes and needs a bitmask.
+    masked: bool,
+    mask: u64,
+}
+
+/// A value to dissect.
+struct Item {
+    /// Display filter name, e.g. `packet.header.len`.
+    abbrev: String,
+    /// Field name shown in the tree.
+    name: String,
+    /// Lua expression for the label of subtrees.
+    title: String,
+    doc: String,
+}
+
+struct Lua {
+    proto: String,
+    /// Name of the struct the dissector is for.
+    root: String,
+    /// Whether values are little-endian (`Lsb0`).
+    le: bool,
+    /// `ProtoField` definitions by variable name, in first-use order.
+    fields: Vec<(String, String)>,
+    /// Dissector functions, assigned to locals declared before all of them.
+    functions: Vec<String>,
+    /// Structs already given a function, with the bit phase it starts at.
+    done: Vec<(String, Option<usize>, String)>,
+}
+
+impl Lua {
+    /// The function dissecting `schema` starting at bit phase `phase`.
+    fn struct_fn(&mut self, schema: &Schema, phase: Option<usize>) -> String {
+        if let Some((_, _, name)) = self
+            .done
+            .iter()
+            .find(|(name, at, _)| *name == schema.name && *at == phase)
+        {
+            return name.clone();
+        }
+        let id = type_id(&schema.name);
+        let name = match phase {
+            Some(0) => format!("dissect_{}", id),
+            Some(phase) => format!("dissect_{}_at{}", id, phase),
+            None => format!("dissect_{}_unaligned", id),
+        };
+        self.done.push((schema.name.clone(), phase, name.clone()));
+        let prefix = if schema.name == self.root {
+            self.proto.clone()
+        } else {
+            format!("{}.{}", self.proto, id)
+        };
+        let has_pointers = schema
+            .fields
+            .iter()
+            .any(|field| field.position != Position::Sequential);
+
+        let mut code = Code::default();
+        for line in schema.doc.lines() {
+            code.line(format!("-- {}", line).trim_end());
+        }
+        code.open(format!("function {}(tvb, tree, bit)", name));
+        code.line("local start = bit");
+        code.line("local v = {}");
+        if has_pointers {
+            code.line("local stop = bit");
+        }
+        let mut current = phase;
+        for field in &schema.fields {
+            self.field(&mut code, schema, field, &prefix, phase, &mut current);
+        }
+        if has_pointers {
+            code.line("return math.max(bit, stop), v");
+        } else {
+            code.line("return bit, v");
+        }
+        code.close("end");
+        self.functions.push(code.out);
+        name
+    }
+
+    fn field(
+        &mut self,
+        code: &mut Code,
+        schema: &Schema,
+        field: &FieldSchema,
+        prefix: &str,
+        start_phase: Option<usize>,
+        phase: &mut Option<usize>,
+    ) {
+        let presence = &field.presence;
+        if presence.skip {
+            return;
+        }
+        let mut conds = Vec::new();
+        if let Some(cond) = &presence.cond {
+            conds.push(lua_expr(cond));
+        }
+        if let Some(version) = &schema.version {
+            let version = lua_expr(version);
+            if let Some(since) = &presence.since {
+                conds.push(format!("{} >= {}", version, lua_expr(since)));
+            }
+            if let Some(until) = &presence.until {
+                conds.push(format!("{} < {}", version, lua_expr(until)));
+            }
+        }
+        let pointer = field.position != Position::Sequential;
+        let block = match conds.len() {
+            0 if pointer => Some(String::from("do")),
+            0 => None,
+            1 => Some(format!("if {} then", conds[0])),
+            _ => Some(format!(
+                "if {} then",
+                conds
+                    .iter()
+                    .map(|cond| format!("({})", cond))
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            )),
+        };
+        if let Some(block) = &block {
+            code.open(block);
+        }
+
+        let mut value_phase = *phase;
+        if pointer {
+            let (target, relative) = match &field.position {
+                Position::OffsetField { field, relative } => (member("v", field), relative),
+                Position::Seek { expr, relative } => (lua_expr(expr), relative),
+                Position::Sequential => unreachable!(),
+            };
+            code.line("local saved = bit");
+            match relative {
+                Relative::Start => {
+                    code.line(format!("bit = start + ({}) * 8", target));
+                    value_phase = start_phase;
+                }
+                Relative::Current => code.line(format!("bit = bit + ({}) * 8", target)),
+            }
+        }
+        if field.pad_before > 0 {
+            code.line(format!("bit = bit + {}", field.pad_before));
+            advance(&mut value_phase, Some(field.pad_before), false);
+        }
+        let align = field.align.filter(|&align| align != 0);
+        if align.is_some() {
+            code.open("do");
+            code.line("local field_start = bit");
+        }
+        let before = value_phase;
+        let count = match &field.size {
+            SizeSource::None => None,
+            SizeSource::Field(name) => Some(Ok(member("v", name))),
+            SizeSource::Func(name) => Some(Err(format!(
+                "count from `{}()` cannot be computed here",
+                name
+            ))),
+        };
+        let item = Item {
+            abbrev: format!("{}.{}", prefix, field.name),
+            name: field.name.clone(),
+            title: format!("{:?}", field.name),
+            doc: field.doc.replace('\n', " "),
+        };
+        self.value(
+            code,
+            &field.layout,
+            &item,
+            &member("v", &field.name),
+            field.bits,
+            &field.aligns,
+            &field.sizes,
+            count,
+            &mut value_phase,
+            0,
+            "tree",
+        );
+        if let Some(align) = align {
+            code.line(format!("bit = align(bit, field_start, {})", align));
+            code.close("end");
+            value_phase =
+                match field
+                    .layout
+                    .sized_bit_width(field.bits, &field.aligns, &field.sizes)
+                {
+                    _ if align.is_multiple_of(8) => before,
+                    Some(width) if field.size == SizeSource::None => {
+                        before.map(|phase| (phase + width.next_multiple_of(align)) % 8)
+                    }
+                    _ => None,
+                };
+        }
+        if field.pad_after > 0 {
+            code.line(format!("bit = bit + {}", field.pad_after));
+            advance(&mut value_phase, Some(field.pad_after), false);
+        }
+        if pointer {
+            code.line("stop = math.max(stop, bit)");
+            code.line("bit = saved");
+        }
+        if block.is_some() {
+            code.close("end");
+        }
+
+        if pointer {
+            return;
+        }
+        *phase = if conds.is_empty() {
+            value_phase
+        } else if whole_bytes_field(field) {
+            *phase
+        } else {
+            None
+        };
+    }
+
+    /// Dissects one value into `target`, adding it to the tree `tree`.
+    ///
+    /// `count` overrides the element count of this level, from `size_field`.
+    #[allow(clippy::too_many_arguments)]
+    fn value(
+        &mut self,
+        code: &mut Code,
+        layout: &Layout,
+        item: &Item,
+        target: &str,
+        bits: Option<usize>,
+        aligns: &[usize],
+        sizes: &[usize],
+        count: Option<Result<String, String>>,
+        phase: &mut Option<usize>,
+        depth: usize,
+        tree: &str,
+    ) {
+        match layout {
+            Layout::Array { item: element, .. } | Layout::Seq { item: element } => {
+                let count = match layout {
+                    Layout::Array { len, .. } => Ok(len.to_string()),
+                    _ => count.unwrap_or_else(|| match sizes.first().filter(|&&size| size != 0) {
+                        Some(size) => Ok(size.to_string()),
+                        None => Err("missing size".into()),
+                    }),
+                };
+                let count = match count {
+                    Ok(count) => count,
+                    Err(msg) => {
+                        code.line(format!("error({:?})", format!("{}: {}", item.name, msg)));
+                        *phase = None;
+                        return;
+                    }
+                };
+                let width = item_width(element, bits, aligns, sizes);
+                let mut inner = match (*phase, width) {
+                    (Some(phase), Some(width)) if width.is_multiple_of(8) => Some(phase),
+                    _ => None,
+                };
+                let d = depth;
+                code.open("do");
+                code.line(format!("local list{} = {{}}", d));
+                code.line(format!(
+                    "local sub{} = {}:add(tvb(bit // 8, 0), {})",
+                    d, tree, item.title
+                ));
+                code.line(format!("local first{} = bit", d));
+                code.open(format!("for i{} = 1, {} do", d, count));
+                let element_item = Item {
+                    abbrev: item.abbrev.clone(),
+                    name: item.name.clone(),
+                    title: format!("\"[\" .. (i{} - 1) .. \"]\"", d),
+                    doc: item.doc.clone(),
+                };
+                self.value(
+                    code,
+                    element,
+                    &element_item,
+                    &format!("list{}[i{}]", d, d),
+                    bits,
+                    aligns.get(1..).unwrap_or_default(),
+                    sizes.get(1..).unwrap_or_default(),
+                    None,
+                    &mut inner,
+                    d + 1,
+                    &format!("sub{}", d),
+                );
+                if let Some(align) = aligns.first().copied().filter(|&align| align != 0) {
+                    code.line(format!("bit = align(bit, first{}, {})", d, align));
+                }
+                code.close("end");
+                code.line(format!(
+                    "sub{}:set_len((bit + 7) // 8 - first{} // 8)",
+                    d, d
+                ));
+                code.line(format!("{} = list{}", target, d));
+                code.close("end");
+                *phase = match (*phase, width, count.parse::<usize>()) {
+                    (Some(phase), Some(width), Ok(count)) => Some((phase + count * width) % 8),
+                    _ if whole_bytes(layout, bits, aligns) => *phase,
+                    _ => None,
+                };
+            }
+            Layout::Tuple(items) => {
+                code.line(format!("{} = {{}}", target));
+                for (i, member_layout) in items.iter().enumerate() {
+                    let member_item = Item {
+                        abbrev: format!("{}_{}", item.abbrev, i),
+                        name: format!("{}.{}", item.name, i),
+                        title: format!("{:?}", format!("{}.{}", item.name, i)),
+                        doc: item.doc.clone(),
+                    };
+                    self.value(
+                        code,
+                        member_layout,
+                        &member_item,
+                        &format!("{}[{}]", target, i + 1),
+                        bits,
+                        aligns,
+                        sizes,
+                        count.clone(),
+                        phase,
+                        depth,
+                        tree,
+                    );
+                }
+            }
+            Layout::Struct(schema) => {
+                let function = self.struct_fn(schema, *phase);
+                code.open("do");
+                code.line(format!(
+                    "local sub = {}:add(tvb(bit // 8, 0), {})",
+                    tree, item.title
+                ));
+                code.line("local first = bit");
+                code.line(format!("bit, {} = {}(tvb, sub, bit)", target, function));
+                code.line("sub:set_len((bit + 7) // 8 - first // 8)");
+                code.close("end");
+                advance(phase, schema.bit_width(), whole_bytes(layout, bits, aligns));
+            }
+            Layout::Opaque => {
+                code.line(format!(
+                    "error({:?})",
+                    format!("{}: no layout to dissect", item.name)
+                ));
+                *phase = None;
+            }
+            _ => self.leaf(code, layout, item, target, bits, phase, tree),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn leaf(
+        &mut self,
+        code: &mut Code,
+        layout: &Layout,
+        item: &Item,
+        target: &str,
+        bits: Option<usize>,
+        phase: &mut Option<usize>,
+        tree: &str,
+    ) {
+        let Some(width) = layout.bit_width(bits, &[]) else {
+            return;
+        };
+        let place = self.placement(*phase, width);
+        let add = if self.le { "add_le" } else { "add" };
+        let doc = if item.doc.is_empty() {
+            String::new()
+        } else {
+            format!(", {:?}", item.doc)
+        };
+        // Values Wireshark can decode from the bytes directly, or with an explicit value.
+        let added = |hf: &str| match &place {
+            Some(place) => format!("{}:{}({}, tvb(bit // 8, {}))", tree, add, hf, place.bytes),
+            None => format!(
+                "{}:add({}, span(tvb, bit, {}), {})",
+                tree, hf, width, target
+            ),
+        };
+        match layout {
+            Layout::Reserved { .. } => {
+                if width > 0 {
+                    code.line(format!(
+                        "{}:add(span(tvb, bit, {}), {:?})",
+                        tree,
+                        width,
+                        format!("{}: reserved ({} bits)", item.name, width)
+                    ));
+                }
+            }
+            Layout::Bool => {
+                let def = match &place {
+                    Some(place) => format!(
+                        "ProtoField.bool({:?}, {:?}, {}, nil, 0x{:x}{})",
+                        item.abbrev,
+                        item.name,
+                        place.bytes * 8,
+                        place.mask,
+                        doc
+                    ),
+                    None => format!(
+                        "ProtoField.bool({:?}, {:?}, base.NONE, nil, nil{})",
+                        item.abbrev, item.name, doc
+                    ),
+                };
+                let hf = self.hf(&item.abbrev, def);
+                code.line(format!("{} = read(tvb, bit, 1) == 1", target));
+                code.line(added(&hf));
+            }
+            Layout::Float {
+                bits: float_bits @ (32 | 64),
+                kind: FloatKind::Ieee,
+            } => {
+                let kind = if *float_bits == 32 { "float" } else { "double" };
+                let def = format!(
+                    "ProtoField.{}({:?}, {:?}{})",
+                    kind, item.abbrev, item.name, doc
+                );
+                let hf = self.hf(&item.abbrev, def);
+                code.line(format!(
+                    "{} = float(read(tvb, bit, {}), {})",
+                    target, width, width
+                ));
+                match &place {
+                    Some(place) if !place.masked => code.line(added(&hf)),
+                    _ => code.line(format!(
+                        "{}:add({}, span(tvb, bit, {}), {})",
+                        tree, hf, width, target
+                    )),
+                }
+            }
+            Layout::Fixed { signed, frac, .. } => {
+                let def = format!(
+                    "ProtoField.double({:?}, {:?}{})",
+                    item.abbrev, item.name, doc
+                );
+                let hf = self.hf(&item.abbrev, def);
+                let raw = if *signed {
+                    format!("signed(read(tvb, bit, {}), {})", width, width)
+                } else {
+                    format!("read(tvb, bit, {})", width)
+                };
+                code.line(format!("{} = {} / 2^{}", target, raw, frac));
+                code.line(format!(
+                    "{}:add({}, span(tvb, bit, {}), {})",
+                    tree, hf, width, target
+                ));
+            }
+            Layout::Int { .. } | Layout::Flags { .. } => {
+                let signed = match layout {
+                    Layout::Flags { repr, .. } => {
+                        matches!(**repr, Layout::Int { signed: true, .. })
+                    }
+                    _ => matches!(layout, Layout::Int { signed: true, .. }),
+                };
+                let field_bits = match &place {
+                    Some(place) => place.bytes * 8,
+                    None => width.next_multiple_of(8),
+                };
+                let field_bits = match field_bits {
+                    0..=8 => 8,
+                    9..=16 => 16,
+                    17..=24 => 24,
+                    25..=32 => 32,
+                    _ => 64,
+                };
+                let flags = match layout {
+                    Layout::Flags { flags, .. } => flags.as_slice(),
+                    _ => &[],
+                };
+                let display = if flags.is_empty() {
+                    "base.DEC"
+                } else {
+                    "base.HEX"
+                };
+                let mask = match &place {
+                    Some(place) if place.masked => format!("0x{:x}", place.mask),
+                    _ => "nil".into(),
+                };
+                let def = format!(
+                    "ProtoField.{}int{}({:?}, {:?}, {}, nil, {}{})",
+                    if signed { "" } else { "u" },
+                    field_bits,
+                    item.abbrev,
+                    item.name,
+                    display,
+                    mask,
+                    doc
+                );
+                let hf = self.hf(&item.abbrev, def);
+                if signed {
+                    code.line(format!(
+                        "{} = signed(read(tvb, bit, {}), {})",
+                        target, width, width
+                    ));
+                } else {
+                    code.line(format!("{} = read(tvb, bit, {})", target, width));
+                }
+                if flags.is_empty() {
+                    code.line(added(&hf));
+                } else {
+                    code.open("do");
+                    code.line(format!("local item = {}", added(&hf)));
+                    for (flag, value) in flags {
+                        let abbrev = format!("{}.{}", item.abbrev, flag.to_ascii_lowercase());
+                        let def = match &place {
+                            Some(place) => format!(
+                                "ProtoField.bool({:?}, {:?}, {}, nil, 0x{:x})",
+                                abbrev,
+                                flag,
+                                place.bytes * 8,
+                                value << place.shift
+                            ),
+                            None => format!("ProtoField.bool({:?}, {:?})", abbrev, flag),
+                        };
+                        let flag_hf = self.hf(&abbrev, def);
+                        match &place {
+                            Some(place) => code.line(format!(
+                                "item:{}({}, tvb(bit // 8, {}))",
+                                add, flag_hf, place.bytes
+                            )),
+                            None => code.line(format!(
+                                "item:add({}, span(tvb, bit, {}), {} & {} == {})",
+                                flag_hf, width, target, value, value
+                            )),
+                        }
+                    }
+                    code.close("end");
+                }
+            }
+            Layout::Float { bits: 16, kind } => {
+                // Wireshark has no 16-bit float fields; decode into a float field by hand.
+                let def = format!(
+                    "ProtoField.float({:?}, {:?}{})",
+                    item.abbrev, item.name, doc
+                );
+                let hf = self.hf(&item.abbrev, def);
+                let value = match kind {
+                    FloatKind::Ieee => format!("float(read(tvb, bit, {}), 16)", width),
+                    // bfloat16 is the upper half of a binary32.
+                    FloatKind::Bfloat => format!("float(read(tvb, bit, {}) << 16, 32)", width),
+                };
+                code.line(format!("{} = {}", target, value));
+                code.line(format!(
+                    "{}:add({}, span(tvb, bit, {}), {})",
+                    tree, hf, width, target
+                ));
+            }
+            _ => {
+                // Other float widths have no decoding; show their raw bits.
+                let def = format!(
+                    "ProtoField.uint{}({:?}, {:?}, base.HEX, nil, nil, {:?})",
+                    width.next_multiple_of(8).clamp(8, 64),
+                    item.abbrev,
+                    item.name,
+                    format!("raw {}-bit float", width)
+                );
+                let hf = self.hf(&item.abbrev, def);
+                code.line(format!("{} = read(tvb, bit, {})", target, width));
+                code.line(format!(
+                    "{}:add({}, span(tvb, bit, {}), {})",
+                    tree, hf, width, target
+                ));
+            }
+        }
+        code.line(format!("bit = bit + {}", width));
+        advance(phase, Some(width), false);
+    }
+
+    /// Where a `width`-bit value at bit phase `phase` sits in the bytes it covers, if known.
+    fn placement(&self, phase: Option<usize>, width: usize) -> Option<Placement> {
+        let phase = phase?;
+        let bytes = (phase + width).div_ceil(8);
+        if bytes > 8 || width == 0 {
+            return None;
+        }
+        let shift = if self.le {
+            phase
+        } else {
+            bytes * 8 - phase - width
+        };
+        let ones = if width == 64 {
+            u64::MAX
+        } else {
+            (1u64 << width) - 1
+        };
+        Some(Placement {
+            bytes,
+            shift,
+            masked: width != bytes * 8,
+            mask: ones << shift,
+        })
+    }
+
+    /// Registers a `ProtoField` definition, returning the Lua expression naming it.
+    fn hf(&mut self, abbrev: &str, def: String) -> String {
+        if let Some((var, _)) = self.fields.iter().find(|(_, known)| *known == def) {
+            return member("hf", var);
+        }
+        let base = abbrev
+            .strip_prefix(&format!("{}.", self.proto))
+            .unwrap_or(abbrev)
+            .replace('.', "_");
+        let mut var = base.clone();
+        let mut n = 2;
+        while self.fields.iter().any(|(known, _)| *known == var) {
+            var = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.fields.push((var.clone(), def));
+        member("hf", &var)
+    }
+}
+
+/// `table.name`, or `table["name"]` for Lua keywords.
+fn member(table: &str, name: &str) -> String {
+    if LUA_KEYWORDS.contains(&name) {
+        format!("{}[{:?}]", table, name)
+    } else {
+        format!("{}.{}", table, name)
+    }
+}
+
+/// Rewrites a Rust expression over the struct's fields as a Lua expression over `v`.
+fn lua_expr(rust: &str) -> String {
+    let ident_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::new();
+    let mut rest = rust;
+    while let Some(c) = rest.chars().next() {
+        let after_ident = out.ends_with(ident_char);
+        if let Some(after) = rest.strip_prefix("self.").filter(|_| !after_ident) {
+            let len = after.find(|c: char| !ident_char(c)).unwrap_or(after.len());
+            out.push_str(&member("v", &after[..len]));
+            rest = &after[len..];
+        } else if let Some(after) = rest.strip_prefix("&&") {
+            out.push_str("and");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("||") {
+            out.push_str("or");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("!=") {
+            out.push_str("~=");
+            rest = after;
+        } else if c == '!' {
+            out.push_str("not ");
+            rest = rest[1..].trim_start();
+        } else if c == '^' {
+            out.push('~');
+            rest = &rest[1..];
+        } else if c == '/' {
+            // Integer division, as on Rust integers.
+            out.push_str("//");
+            rest = &rest[1..];
+        } else if let Some(after) = rest.strip_prefix(".len()") {
+            let path_start = out
+                .rfind(|c: char| !(ident_char(c) || matches!(c, '.' | '[' | ']' | '"')))
+                .map_or(0, |i| i + 1);
+            out.insert(path_start, '#');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(".bits()") {
+            // Flag sets are read as their raw integer already.
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(" as ") {
+            // Casts only change Rust types.
+            rest = after.trim_start_matches(ident_char);
+        } else if c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()) && after_ident {
+            // Tuple members are 1-based tables.
+            let digits = rest[1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len() - 1);
+            let index: usize = rest[1..1 + digits].parse().unwrap_or_default();
+            let _ = write!(out, "[{}]", index + 1);
+            rest = &rest[1 + digits..];
+        } else if c.is_ascii_digit() && !after_ident {
+            let len = rest.find(|c: char| !ident_char(c)).unwrap_or(rest.len());
+            let literal = &rest[..len];
+            let literal = INT_SUFFIXES
+                .iter()
+                .find_map(|suffix| literal.strip_suffix(suffix))
+                .filter(|digits| !digits.is_empty())
+                .unwrap_or(literal);
+            out.extend(literal.chars().filter(|&c| c != '_'));
+            rest = &rest[len..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
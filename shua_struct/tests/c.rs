@@ -0,0 +1,159 @@
+#![cfg(all(feature = "collections", feature = "int", feature = "bool"))]
+
+mod common;
+
+use common::golden;
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Lsb0};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, BinaryStruct)]
+#[binary_struct(repr_c)]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// A telemetry record shared with C code.
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(repr_c)]
+pub struct Record {
+    pub kind: u8,
+    pub id: u32,
+    #[binary_field(bits = 3)]
+    pub level: u8,
+    #[binary_field(bits = 4)]
+    pub channel: u8,
+    pub enabled: bool,
+    pub origin: Point,
+    pub seq: u16,
+    pub tail: u8,
+}
+
+fn record() -> Record {
+    Record {
+        kind: 0xAA,
+        id: 0x0403_0201,
+        level: 5,
+        channel: 9,
+        enabled: true,
+        origin: Point {
+            x: 0x0605,
+            y: 0x0807,
+        },
+        seq: 0x0A09,
+        tail: 0xBB,
+    }
+}
+
+/// `kind`, 3 pad bytes, `id`, the `level`/`channel`/`enabled` unit, a pad byte,
+/// `origin`, `seq`, `tail` and 3 bytes up to the struct's 4-byte alignment.
+const RECORD_BYTES: [u8; 20] = [
+    0xAA, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0xCD, 0x00, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A,
+    0xBB, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn repr_c_inserts_natural_padding() {
+    assert_eq!(<Record as BinaryField<Lsb0>>::C_ALIGN, 32);
+    assert_eq!(record().build(&None).unwrap().into_vec(), RECORD_BYTES);
+
+    let schema = Record::schema();
+    let pads: Vec<_> = schema
+        .fields
+        .iter()
+        .map(|f| (f.name.as_str(), f.pad_before, f.pad_after))
+        .collect();
+    assert_eq!(
+        pads,
+        [
+            ("kind", 0, 0),
+            ("id", 24, 0),
+            ("level", 0, 0),
+            ("channel", 0, 0),
+            ("enabled", 0, 0),
+            ("origin", 8, 0),
+            ("seq", 0, 0),
+            ("tail", 0, 24),
+        ]
+    );
+}
+
+#[test]
+fn repr_c_round_trips() {
+    let bits = BitSlice::<u8, Lsb0>::from_slice(&RECORD_BYTES);
+    let (parsed, len) = Record::parse(bits, &None).unwrap();
+    assert_eq!(parsed, record());
+    assert_eq!(len, RECORD_BYTES.len() * 8);
+    assert_eq!(parsed.build(&None).unwrap().into_vec(), RECORD_BYTES);
+}
+
+#[test]
+fn header_matches_golden() {
+    golden("record.h", &shua_struct::c::header(&Record::schema()));
+}
+
+#[test]
+fn containers_align_like_their_elements() {
+    assert_eq!(<Vec<u32> as BinaryField<Lsb0>>::C_ALIGN, 32);
+    assert_eq!(<BTreeMap<u8, u32> as BinaryField<Lsb0>>::C_ALIGN, 32);
+    assert_eq!(<BTreeMap<u8, u8> as BinaryField<Lsb0>>::C_ALIGN, 8);
+    assert_eq!(<HashMap<u16, bool> as BinaryField<Lsb0>>::C_ALIGN, 16);
+    #[cfg(feature = "smallvec")]
+    assert_eq!(
+        <smallvec::SmallVec<[u32; 2]> as BinaryField<Lsb0>>::C_ALIGN,
+        32
+    );
+    #[cfg(feature = "arrayvec")]
+    assert_eq!(
+        <arrayvec::ArrayVec<u16, 2> as BinaryField<Lsb0>>::C_ALIGN,
+        16
+    );
+}
+
+#[cfg(all(feature = "smallvec", feature = "arrayvec"))]
+#[test]
+fn inline_vectors_are_padded_like_vec() {
+    #[derive(Debug, Default, PartialEq, BinaryStruct)]
+    #[binary_struct(repr_c)]
+    pub struct VecItems {
+        pub tag: u8,
+        #[binary_field(sizes = [2])]
+        pub items: Vec<u16>,
+    }
+
+    #[derive(Debug, Default, PartialEq, BinaryStruct)]
+    #[binary_struct(repr_c)]
+    pub struct SmallItems {
+        pub tag: u8,
+        #[binary_field(sizes = [2])]
+        pub items: smallvec::SmallVec<[u16; 2]>,
+    }
+
+    #[derive(Debug, Default, PartialEq, BinaryStruct)]
+    #[binary_struct(repr_c)]
+    pub struct ArrayItems {
+        pub tag: u8,
+        #[binary_field(sizes = [2])]
+        pub items: arrayvec::ArrayVec<u16, 2>,
+    }
+
+    let expected = [0x07, 0x00, 0x01, 0x02, 0x03, 0x04];
+    let vec = VecItems {
+        tag: 7,
+        items: vec![0x0201, 0x0403],
+    };
+    assert_eq!(vec.build(&None).unwrap().into_vec(), expected);
+    let small = SmallItems {
+        tag: 7,
+        items: vec.items.iter().copied().collect(),
+    };
+    assert_eq!(small.build(&None).unwrap().into_vec(), expected);
+    let array = ArrayItems {
+        tag: 7,
+        items: vec.items.iter().copied().collect(),
+    };
+    assert_eq!(array.build(&None).unwrap().into_vec(), expected);
+    let bits = BitSlice::<u8, Lsb0>::from_slice(&expected);
+    assert_eq!(SmallItems::parse(bits, &None).unwrap().0, small);
+    assert_eq!(ArrayItems::parse(bits, &None).unwrap().0, array);
+}
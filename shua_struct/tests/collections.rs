@@ -0,0 +1,165 @@
+#![cfg(all(feature = "collections", feature = "int"))]
+
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Error, Lsb0};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Table {
+    pub count: u8,
+    #[binary_field(size_field = count)]
+    pub entries: BTreeMap<u8, u16>,
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Lookup {
+    pub count: u8,
+    #[binary_field(size_field = count)]
+    pub entries: HashMap<u8, u16>,
+}
+
+fn parse<T: BinaryField<Lsb0>>(bytes: &[u8]) -> Result<(T, usize), Error> {
+    T::parse(BitSlice::<u8, Lsb0>::from_slice(bytes), &None)
+}
+
+#[test]
+fn btree_map_is_a_sequence_of_pairs() {
+    let bytes = [2, 1, 0x10, 0x00, 3, 0x30, 0x00];
+    let (table, len) = parse::<Table>(&bytes).unwrap();
+    assert_eq!(len, 56);
+    assert_eq!(table.entries, BTreeMap::from([(1, 0x10), (3, 0x30)]));
+    assert_eq!(table.build(&None).unwrap().into_vec(), bytes);
+}
+
+#[test]
+fn hash_map_is_a_sequence_of_pairs() {
+    let bytes = [1, 7, 0x34, 0x12];
+    let (lookup, _) = parse::<Lookup>(&bytes).unwrap();
+    assert_eq!(lookup.entries, HashMap::from([(7, 0x1234)]));
+    assert_eq!(lookup.build(&None).unwrap().into_vec(), bytes);
+
+    // Entries build in iteration order, so compare after a round trip.
+    let lookup = Lookup {
+        count: 3,
+        entries: HashMap::from([(1, 10), (2, 20), (3, 30)]),
+    };
+    let bytes = lookup.build(&None).unwrap().into_vec();
+    assert_eq!(bytes.len(), 10);
+    assert_eq!(parse::<Lookup>(&bytes).unwrap().0, lookup);
+}
+
+#[test]
+fn duplicate_keys_are_rejected() {
+    let bytes = [2, 5, 0x01, 0x00, 5, 0x02, 0x00];
+    let err = parse::<Table>(&bytes).unwrap_err();
+    assert_eq!(err.path, "entries[1]");
+    assert_eq!(err.bit, 32);
+    assert_eq!(err.message, "BTreeMap parse error: duplicate key");
+
+    let err = parse::<Lookup>(&bytes).unwrap_err();
+    assert_eq!(err.path, "entries[1]");
+    assert_eq!(err.message, "HashMap parse error: duplicate key");
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Queues {
+    pub len: u8,
+    #[binary_field(size_field = len)]
+    pub deque: VecDeque<u8>,
+    #[binary_field(size_field = len)]
+    pub boxed: Box<[u16]>,
+}
+
+#[test]
+fn vec_deque_and_boxed_slice_round_trip() {
+    let bytes = [2, 0xAA, 0xBB, 0x01, 0x00, 0x02, 0x00];
+    let (queues, len) = parse::<Queues>(&bytes).unwrap();
+    assert_eq!(len, 56);
+    assert_eq!(queues.deque, VecDeque::from([0xAA, 0xBB]));
+    assert_eq!(*queues.boxed, [1, 2]);
+    assert_eq!(queues.build(&None).unwrap().into_vec(), bytes);
+
+    let err = parse::<Queues>(&bytes[..5]).unwrap_err();
+    assert_eq!(err.path, "boxed[1]");
+    assert_eq!(err.bit, 40);
+}
+
+/// A tree node whose children are boxed nodes.
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Node {
+    pub value: u8,
+    pub count: u8,
+    #[binary_field(size_field = count)]
+    pub children: Vec<Box<Node>>,
+}
+
+#[test]
+fn boxed_structs_recurse() {
+    let leaf = |value| {
+        Box::new(Node {
+            value,
+            count: 0,
+            children: Vec::new(),
+        })
+    };
+    let tree = Node {
+        value: 1,
+        count: 2,
+        children: vec![
+            Box::new(Node {
+                value: 2,
+                count: 1,
+                children: vec![leaf(3)],
+            }),
+            leaf(4),
+        ],
+    };
+    let bytes = [1, 2, 2, 1, 3, 0, 4, 0];
+    assert_eq!(tree.build(&None).unwrap().into_vec(), bytes);
+    assert_eq!(parse::<Node>(&bytes).unwrap(), (tree, 64));
+
+    let err = parse::<Node>(&bytes[..5]).unwrap_err();
+    assert_eq!(err.path, "children[0].children[0].count");
+    assert_eq!(err.bit, 40);
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn small_vec_round_trips_past_its_inline_capacity() {
+    #[derive(Debug, Default, PartialEq, BinaryStruct)]
+    pub struct Small {
+        pub len: u8,
+        #[binary_field(size_field = len)]
+        pub items: smallvec::SmallVec<[u8; 2]>,
+    }
+
+    for bytes in [&[1, 9][..], &[3, 7, 8, 9]] {
+        let (small, _) = parse::<Small>(bytes).unwrap();
+        assert_eq!(small.items.as_slice(), &bytes[1..]);
+        assert_eq!(small.items.spilled(), bytes.len() > 3);
+        assert_eq!(small.build(&None).unwrap().into_vec(), bytes);
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+#[test]
+fn array_vec_over_capacity_is_an_error() {
+    #[derive(Debug, Default, PartialEq, BinaryStruct)]
+    pub struct Bounded {
+        pub len: u8,
+        #[binary_field(size_field = len)]
+        pub items: arrayvec::ArrayVec<u8, 2>,
+    }
+
+    let bytes = [2, 7, 8];
+    let (bounded, _) = parse::<Bounded>(&bytes).unwrap();
+    assert_eq!(bounded.items.as_slice(), [7, 8]);
+    assert_eq!(bounded.build(&None).unwrap().into_vec(), bytes);
+
+    let err = parse::<Bounded>(&[3, 7, 8, 9]).unwrap_err();
+    assert_eq!(err.path, "items");
+    assert_eq!(err.bit, 8);
+    assert_eq!(
+        err.message,
+        "ArrayVec parse error: size 3 exceeds capacity 2"
+    );
+}
@@ -0,0 +1,87 @@
+//! Types and helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use shua_struct::BinaryStruct;
+use std::path::Path;
+
+/// Compares `actual` with the file `tests/golden/{name}`; with `SHUA_BLESS=1` set,
+/// rewrites the file instead.
+pub fn golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    if std::env::var_os("SHUA_BLESS").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "{}: {}; run with SHUA_BLESS=1 to create it",
+            path.display(),
+            err
+        )
+    });
+    assert_eq!(actual, expected, "{} is out of date", path.display());
+}
+
+// The example's save format.
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Item {
+    pub id: u16,
+    pub quantity: u8,
+    pub value: f32,
+    #[binary_field(align = 8)]
+    pub flags: [bool; 4],
+}
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Inventory {
+    #[binary_field(calc = "self.items.len() as u8", verify_calc)]
+    pub max_slots: u8,
+    #[binary_field(size_func = get_actual_slots)]
+    pub items: Vec<Item>,
+}
+
+impl Inventory {
+    fn get_actual_slots(&self) -> usize {
+        self.max_slots as usize
+    }
+}
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Player {
+    pub player_id: u32,
+    pub level: u8,
+    #[binary_field(size_field = level)]
+    pub skills: Vec<Skill>,
+    pub inventory: Inventory,
+}
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Skill {
+    pub skill_id: u8,
+    pub points: u8,
+    pub multiplier: f32,
+    #[binary_field(align = 8)]
+    pub modifiers: [bool; 2],
+}
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0, version = version)]
+pub struct GameSave {
+    pub version: u16,
+    #[binary_field(since = 2)]
+    pub play_time: u32,
+    #[binary_field(calc = "self.players.len() as u8")]
+    pub player_count: u8,
+    #[binary_field(size_field = player_count)]
+    pub players: Vec<Player>,
+    #[binary_field(align = 8)]
+    pub options: [bool; 6],
+}
@@ -0,0 +1,49 @@
+#![cfg(all(feature = "array", feature = "int"))]
+
+use shua_struct::BinaryStruct;
+
+/// A sensor reading.
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Reading {
+    /// Format version.
+    #[binary_field(bits = 4)]
+    pub version: u8,
+    #[binary_field(bits = 4, pad_bits_after = 8)]
+    pub channel: u8,
+    /// Sample count.
+    pub count: u16,
+    #[binary_field(size_field = count)]
+    pub samples: Vec<i16>,
+}
+
+#[test]
+fn markdown_documents_a_struct() {
+    assert_eq!(
+        shua_struct::docgen::markdown(&Reading::schema()),
+        "\
+## Reading
+
+A sensor reading.
+
+| Offset | Width | Field | Type | Byte order | Layout | Description |
+|---|---|---|---|---|---|---|
+| 0 | 4 | `version` | `u8` | — | 4 bits wide | Format version. |
+| 4 | 12 | `channel` | `u8` | — | 4 bits wide; 8 padding bits after |  |
+| 16 | 16 | `count` | `u16` | big-endian |  | Sample count. |
+| 32 | `count` × 16 | `samples` | `Vec<i16>` | big-endian | count from `count` |  |
+
+Total: depends on the data.
+
+```text
+ 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|version|channel|     (pad)     |             count             |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+```
+
+Later fields have data-dependent positions and are not drawn.
+"
+    );
+}
@@ -0,0 +1,90 @@
+#![cfg(all(feature = "bool", feature = "int"))]
+
+use shua_struct::dump::{BitRange, bit_diagram, hex_dump, span_diagram};
+use shua_struct::{BinaryStruct, BitSlice, Lsb0, Span};
+
+#[derive(Debug, Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Header {
+    #[binary_field(bits = 4)]
+    pub version: u8,
+    #[binary_field(bits = 4)]
+    pub kind: u8,
+    pub len: u16,
+    #[binary_field(pad_bytes = 1)]
+    pub id: u8,
+    pub urgent: bool,
+}
+
+const BYTES: [u8; 7] = [0x21, 0x34, 0x12, 0x00, 0x07, 0x01, 0xFF];
+
+fn trace() -> Span<Lsb0> {
+    let (header, span) = Span::<Lsb0>::trace::<Header>(BitSlice::from_slice(&BYTES), &None);
+    header.unwrap();
+    span
+}
+
+#[test]
+fn hex_dump_labels_fields_padding_and_leftovers() {
+    assert_eq!(
+        hex_dump(&BYTES, &trace(), false),
+        "\
+00000000  21                                               version = 1, kind = 2
+00000001  34 12                                            len = 4660
+00000003  00                                               (padding)
+00000004  07                                               id = 7
+00000005  01                                               urgent = true
+00000006  ff                                               (unparsed)
+"
+    );
+}
+
+#[test]
+fn hex_dump_colors_fields_only() {
+    let dump = hex_dump(&BYTES, &trace(), true);
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(
+        lines[1],
+        "00000001  \x1b[32m34 12                                            len = 4660\x1b[0m"
+    );
+    assert_eq!(
+        lines[2],
+        "00000003  00                                               (padding)"
+    );
+}
+
+#[test]
+fn span_diagram_draws_leaf_fields() {
+    assert_eq!(
+        span_diagram(&trace()),
+        " 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|version| kind  |              len              |               |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|      id       |u|
++-+-+-+-+-+-+-+-+-+
+"
+    );
+}
+
+#[test]
+fn bit_diagram_continues_ranges_across_rows() {
+    let range = |label: &str, start_bit, len_bits| BitRange {
+        label: label.into(),
+        start_bit,
+        len_bits,
+    };
+    assert_eq!(
+        bit_diagram(&[range("flags", 0, 3), range("length", 8, 32)]),
+        " 0                   1                   2                   3
+ 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|flags|         |                    length                     |
++-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+|    length     |
++-+-+-+-+-+-+-+-+
+"
+    );
+    assert_eq!(bit_diagram(&[]), "");
+}
@@ -0,0 +1,180 @@
+#![cfg(all(feature = "dynamic", feature = "tuple"))]
+
+use shua_struct::dynamic::{self, DynamicValue};
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Schema};
+
+/// Parses `bytes` with `schema` at runtime and checks that building the value
+/// gives the same bytes back.
+fn dynamic_round_trip(schema: &Schema, bytes: &[u8]) -> DynamicValue {
+    let value = dynamic::from_bytes(schema, bytes).unwrap();
+    assert_eq!(dynamic::to_bytes(schema, &value).unwrap(), bytes);
+    value
+}
+
+fn int(value: i128) -> DynamicValue {
+    DynamicValue::Int(value)
+}
+
+fn ints(values: &[i128]) -> DynamicValue {
+    DynamicValue::List(values.iter().copied().map(int).collect())
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Bits {
+    #[binary_field(bits = 3)]
+    pub kind: u8,
+    pub urgent: bool,
+    #[binary_field(bits = 12)]
+    pub id: u16,
+    #[binary_field(bits = 5, pad_bits_after = 3)]
+    pub delta: i8,
+    #[binary_field(align = 16)]
+    pub pair: (bool, u8),
+    pub level: i16,
+}
+
+#[test]
+fn bits_agree() {
+    let bits = Bits {
+        kind: 5,
+        urgent: true,
+        id: 0xABC,
+        delta: -3,
+        pair: (true, 0x42),
+        level: -300,
+    };
+    let bytes = bits.build(&None).unwrap().into_vec();
+    let value = dynamic_round_trip(&Bits::schema(), &bytes);
+    assert_eq!(
+        value,
+        DynamicValue::Struct(vec![
+            ("kind".into(), int(5)),
+            ("urgent".into(), DynamicValue::Bool(true)),
+            ("id".into(), int(0xABC)),
+            ("delta".into(), int(-3)),
+            (
+                "pair".into(),
+                DynamicValue::List(vec![DynamicValue::Bool(true), int(0x42)])
+            ),
+            ("level".into(), int(-300)),
+        ])
+    );
+    let (parsed, _) = Bits::parse(BitSlice::from_slice(&bytes), &None).unwrap();
+    assert_eq!(parsed, bits);
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Cell {
+    #[binary_field(bits = 4)]
+    pub x: u8,
+    pub set: bool,
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Sized {
+    #[binary_field(calc = "self.words.len() as u8")]
+    pub count: u8,
+    #[binary_field(size_field = count)]
+    pub words: Vec<u16>,
+    #[binary_field(align = 8, sub_align = 1)]
+    pub cells: [Cell; 3],
+    #[binary_field(sizes = [2, 2], aligns = [0, 4])]
+    pub grid: Vec<Vec<bool>>,
+    #[binary_field(align = 8)]
+    pub tail: [bool; 3],
+}
+
+#[test]
+fn sizes_and_alignment_agree() {
+    let sized = Sized {
+        count: 2,
+        words: vec![0x1234, 0xFFFF],
+        cells: [
+            Cell { x: 1, set: true },
+            Cell { x: 15, set: false },
+            Cell { x: 7, set: true },
+        ],
+        grid: vec![vec![true, false], vec![false, true]],
+        tail: [true, true, false],
+    };
+    let bytes = sized.build(&None).unwrap().into_vec();
+    let value = dynamic_round_trip(&Sized::schema(), &bytes);
+    assert_eq!(value.get("count"), Some(&int(2)));
+    assert_eq!(value.get("words"), Some(&ints(&[0x1234, 0xFFFF])));
+    let cells = value.get("cells").unwrap().as_list().unwrap();
+    assert_eq!(cells[1].get("x"), Some(&int(15)));
+    assert_eq!(cells[2].get("set"), Some(&DynamicValue::Bool(true)));
+    let (parsed, len) = Sized::parse(BitSlice::from_slice(&bytes), &None).unwrap();
+    assert_eq!(parsed, sized);
+    assert_eq!(len, bytes.len() * 8);
+
+    let empty = Sized {
+        count: 0,
+        words: Vec::new(),
+        ..sized
+    };
+    let bytes = empty.build(&None).unwrap().into_vec();
+    let value = dynamic_round_trip(&Sized::schema(), &bytes);
+    assert_eq!(value.get("words"), Some(&ints(&[])));
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0, version = version)]
+pub struct Versioned {
+    pub version: u8,
+    pub flags: u8,
+    #[binary_field(cond = "self.flags & 1 != 0")]
+    pub extra: u16,
+    #[binary_field(since = 2)]
+    pub added: u32,
+    #[binary_field(until = 2)]
+    pub legacy: u8,
+    #[binary_field(skip, default = "7")]
+    pub cached: u8,
+}
+
+#[test]
+fn cond_and_versions_agree() {
+    for (version, flags) in [(1, 0), (1, 1), (2, 0), (3, 1)] {
+        let versioned = Versioned {
+            version,
+            flags,
+            extra: if flags & 1 != 0 { 0xBEEF } else { 0 },
+            added: if version >= 2 { 0xDEAD_BEEF } else { 0 },
+            legacy: if version < 2 { 9 } else { 0 },
+            cached: 7,
+        };
+        let bytes = versioned.build(&None).unwrap().into_vec();
+        let value = dynamic_round_trip(&Versioned::schema(), &bytes);
+        let present = |name: &str| value.get(name).is_some();
+        assert_eq!(present("extra"), flags & 1 != 0, "{:?}", value);
+        assert_eq!(present("added"), version >= 2, "{:?}", value);
+        assert_eq!(present("legacy"), version < 2, "{:?}", value);
+        assert!(!present("cached"));
+        let (parsed, _) = Versioned::parse(BitSlice::from_slice(&bytes), &None).unwrap();
+        assert_eq!(parsed, versioned);
+    }
+}
+
+#[test]
+fn dynamic_values_build_like_the_derive() {
+    let value = DynamicValue::Struct(vec![
+        ("version".into(), int(2)),
+        ("flags".into(), int(1)),
+        ("extra".into(), int(0x0102)),
+        ("added".into(), int(0x0304_0506)),
+    ]);
+    let bytes = dynamic::to_bytes(&Versioned::schema(), &value).unwrap();
+    let expected = Versioned {
+        version: 2,
+        flags: 1,
+        extra: 0x0102,
+        added: 0x0304_0506,
+        legacy: 0,
+        cached: 7,
+    };
+    assert_eq!(bytes, expected.build(&None).unwrap().into_vec());
+}
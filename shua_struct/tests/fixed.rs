@@ -0,0 +1,90 @@
+#![cfg(feature = "fixed")]
+
+use shua_struct::field::fixed::{Q15, Q16_16, UQ8_8};
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Fixed, Lsb0, Msb0};
+
+fn round_trip<T: BinaryField<Lsb0> + PartialEq + core::fmt::Debug>(value: T, bytes: &[u8]) {
+    assert_eq!(value.build(&None).unwrap().into_vec(), bytes);
+    let (parsed, len) = T::parse(BitSlice::<u8, Lsb0>::from_slice(bytes), &None).unwrap();
+    assert_eq!(parsed, value);
+    assert_eq!(len, bytes.len() * 8);
+}
+
+#[test]
+fn q15_covers_minus_one_to_just_under_one() {
+    let min = Q15::from_f64(-1.0);
+    assert_eq!(min.to_bits(), i16::MIN);
+    assert_eq!(min.to_f64(), -1.0);
+    round_trip(min, &[0x00, 0x80]);
+
+    let max = Q15::from_bits(i16::MAX);
+    assert_eq!(max.to_f64(), 1.0 - 1.0 / 32768.0);
+    assert_eq!(Q15::from_f64(1.0 - 1.0 / 32768.0), max);
+    round_trip(max, &[0xFF, 0x7F]);
+
+    // Out of range values saturate.
+    assert_eq!(Q15::from_f64(1.0), max);
+    assert_eq!(Q15::from_f64(-2.0), min);
+}
+
+#[test]
+fn uq8_8_converts() {
+    let value = UQ8_8::from_f64(3.75);
+    assert_eq!(value.to_bits(), 0x03C0);
+    assert_eq!(f64::from(value), 3.75);
+    assert_eq!(value.to_f32(), 3.75);
+    assert_eq!(UQ8_8::from_f32(0.5).to_bits(), 0x0080);
+    // Rounds to the nearest 1/256.
+    assert_eq!(UQ8_8::from_f64(1.0 / 1024.0).to_bits(), 0);
+    assert_eq!(UQ8_8::from_f64(1.0 / 384.0).to_bits(), 1);
+    round_trip(value, &[0xC0, 0x03]);
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(bit_order = Msb0)]
+pub struct Reading {
+    #[binary_field(bits = 4)]
+    pub tag: u8,
+    /// Q16.16 in 20 bits: 4 integer bits and the sign.
+    #[binary_field(bits = 20)]
+    pub offset: Q16_16,
+}
+
+#[test]
+fn negative_q16_16_sign_extends_from_bits() {
+    let reading = Reading {
+        tag: 0xA,
+        offset: Q16_16::from_f64(-1.5),
+    };
+    assert_eq!(reading.offset.to_bits(), -0x18000);
+    // 0xA, then -0x18000 in 20 bits: 0xE8000.
+    let bytes = reading.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0xAE, 0x80, 0x00]);
+
+    let (parsed, len) = Reading::parse(BitSlice::<u8, Msb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(len, 24);
+    assert_eq!(parsed, reading);
+    assert_eq!(parsed.offset.to_f64(), -1.5);
+}
+
+#[test]
+fn value_wider_than_bits_fails_the_build() {
+    let reading = Reading {
+        tag: 0,
+        offset: Q16_16::from_f64(8.0),
+    };
+    let err = reading.build(&None).unwrap_err();
+    assert_eq!(err.path, "offset");
+    assert_eq!(err.bit, 4);
+    assert_eq!(
+        err.message,
+        "i32 build error: 524288 does not fit in 20 bits"
+    );
+
+    // The widest value that fits still builds.
+    let fits = Reading {
+        tag: 0,
+        offset: Fixed::from_bits(0x7FFFF),
+    };
+    assert!(fits.build(&None).is_ok());
+}
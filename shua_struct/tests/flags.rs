@@ -0,0 +1,117 @@
+#![cfg(all(feature = "bitflags", feature = "bool"))]
+
+use bitflags::bitflags;
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Lsb0};
+
+bitflags! {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinaryStruct)]
+    #[binary_struct(flags, unknown = "reject")]
+    pub struct Strict: u8 {
+        const READ = 0x01;
+        const WRITE = 0x02;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinaryStruct)]
+    #[binary_struct(flags, unknown = "retain")]
+    pub struct Kept: u8 {
+        const READ = 0x01;
+        const WRITE = 0x02;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinaryStruct)]
+    #[binary_struct(flags, unknown = "truncate")]
+    pub struct Dropped: u8 {
+        const READ = 0x01;
+        const WRITE = 0x02;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinaryStruct)]
+    #[binary_struct(flags, bits = 3)]
+    pub struct Mode: u8 {
+        const R = 0b100;
+        const W = 0b010;
+        const X = 0b001;
+    }
+}
+
+/// `READ` plus the undeclared bit 0x80.
+const WIRE: [u8; 1] = [0x81];
+
+fn parse<T: BinaryField<Lsb0>>(bytes: &[u8]) -> Result<T, shua_struct::Error> {
+    T::parse(BitSlice::<u8, Lsb0>::from_slice(bytes), &None).map(|(value, _)| value)
+}
+
+#[test]
+fn reject_fails_on_unknown_bits() {
+    let err = parse::<Strict>(&WIRE).unwrap_err();
+    assert!(
+        err.message.ends_with("parse error: unknown flag bits"),
+        "{}",
+        err
+    );
+    let err = Strict::from_bits_retain(0x81).build(&None).unwrap_err();
+    assert!(
+        err.message.ends_with("build error: unknown flag bits"),
+        "{}",
+        err
+    );
+
+    assert_eq!(
+        parse::<Strict>(&[0x03]).unwrap(),
+        Strict::READ | Strict::WRITE
+    );
+}
+
+#[test]
+fn retain_keeps_unknown_bits_through_a_build() {
+    let flags = parse::<Kept>(&WIRE).unwrap();
+    assert_eq!(flags.bits(), 0x81);
+    assert!(flags.contains(Kept::READ));
+    assert_eq!(flags.build(&None).unwrap().into_vec(), WIRE);
+}
+
+#[test]
+fn truncate_drops_unknown_bits() {
+    let flags = parse::<Dropped>(&WIRE).unwrap();
+    assert_eq!(flags, Dropped::READ);
+    assert_eq!(flags.build(&None).unwrap().into_vec(), [0x01]);
+    assert_eq!(
+        Dropped::from_bits_retain(0x82)
+            .build(&None)
+            .unwrap()
+            .into_vec(),
+        [0x02]
+    );
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Entry {
+    pub mode: Mode,
+    #[binary_field(bits = 4)]
+    pub owner: u8,
+    pub hidden: bool,
+}
+
+#[test]
+fn three_bit_flags_pack_with_their_neighbors() {
+    let entry = Entry {
+        mode: Mode::R | Mode::X,
+        owner: 0xA,
+        hidden: true,
+    };
+    // mode in bits 0..3, owner in 3..7, hidden in bit 7.
+    let bytes = entry.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0b1101_0101]);
+    let (parsed, len) = Entry::parse(BitSlice::<u8, Lsb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(len, 8);
+    assert_eq!(parsed, entry);
+
+    let fields = Entry::schema().fields;
+    assert_eq!(fields[0].layout.bit_width(None, &[]), Some(3));
+}
@@ -0,0 +1,98 @@
+#![cfg(all(feature = "dynamic", feature = "half"))]
+
+use shua_struct::dynamic::{self, DynamicValue};
+use shua_struct::{BinaryField, BinaryStruct, FloatKind, Layout, bf16, f16};
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Halves {
+    pub ieee: f16,
+    pub brain: bf16,
+}
+
+#[test]
+fn half_floats_report_their_kind() {
+    let schema = Halves::schema();
+    assert_eq!(
+        schema.fields[0].layout,
+        Layout::Float {
+            bits: 16,
+            kind: FloatKind::Ieee
+        }
+    );
+    assert_eq!(
+        schema.fields[1].layout,
+        Layout::Float {
+            bits: 16,
+            kind: FloatKind::Bfloat
+        }
+    );
+}
+
+#[test]
+fn dynamic_half_floats_match_the_derive() {
+    let halves = Halves {
+        ieee: f16::from_f32(1.5),
+        brain: bf16::from_f32(-2.0),
+    };
+    let bytes = halves.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0x00, 0x3E, 0x00, 0xC0]);
+
+    let value = dynamic::from_bytes(&Halves::schema(), &bytes).unwrap();
+    assert_eq!(value.get("ieee"), Some(&DynamicValue::Float(1.5)));
+    assert_eq!(value.get("brain"), Some(&DynamicValue::Float(-2.0)));
+    assert_eq!(dynamic::to_bytes(&Halves::schema(), &value).unwrap(), bytes);
+}
+
+#[derive(Debug, Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct NansLsb {
+    pub ieee: f16,
+    pub brain: bf16,
+    pub single: f32,
+}
+
+#[derive(Debug, Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct NansMsb {
+    pub ieee: f16,
+    pub brain: bf16,
+    pub single: f32,
+}
+
+/// Signaling NaNs (quiet bit clear) with a nonzero payload.
+const F16_SNAN: u16 = 0x7C15;
+const BF16_SNAN: u16 = 0xFF95;
+const F32_SNAN: u32 = 0x7FA1_2345;
+
+#[test]
+fn signaling_nan_payloads_round_trip() {
+    let ieee = f16::from_bits(F16_SNAN);
+    let brain = bf16::from_bits(BF16_SNAN);
+    let single = f32::from_bits(F32_SNAN);
+    assert!(ieee.is_nan() && brain.is_nan() && single.is_nan());
+
+    let lsb = NansLsb {
+        ieee,
+        brain,
+        single,
+    };
+    let bytes = lsb.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0x15, 0x7C, 0x95, 0xFF, 0x45, 0x23, 0xA1, 0x7F]);
+    let (parsed, _) = NansLsb::parse(shua_struct::BitSlice::from_slice(&bytes), &None).unwrap();
+    assert_eq!(parsed.ieee.to_bits(), F16_SNAN);
+    assert_eq!(parsed.brain.to_bits(), BF16_SNAN);
+    assert_eq!(parsed.single.to_bits(), F32_SNAN);
+
+    let msb = NansMsb {
+        ieee,
+        brain,
+        single,
+    };
+    let bytes = msb.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0x7C, 0x15, 0xFF, 0x95, 0x7F, 0xA1, 0x23, 0x45]);
+    let (parsed, _) = NansMsb::parse(shua_struct::BitSlice::from_slice(&bytes), &None).unwrap();
+    assert_eq!(parsed.ieee.to_bits(), F16_SNAN);
+    assert_eq!(parsed.brain.to_bits(), BF16_SNAN);
+    assert_eq!(parsed.single.to_bits(), F32_SNAN);
+}
@@ -0,0 +1,212 @@
+-- Wireshark dissector for `GameSave`, generated by shua_struct.
+
+local proto = Proto("game_save", "GameSave")
+
+local hf = {}
+hf.version = ProtoField.uint16("game_save.version", "version", base.DEC, nil, nil)
+hf.play_time = ProtoField.uint32("game_save.play_time", "play_time", base.DEC, nil, nil)
+hf.player_count = ProtoField.uint8("game_save.player_count", "player_count", base.DEC, nil, nil)
+hf.player_player_id = ProtoField.uint32("game_save.player.player_id", "player_id", base.DEC, nil, nil)
+hf.player_level = ProtoField.uint8("game_save.player.level", "level", base.DEC, nil, nil)
+hf.skill_skill_id = ProtoField.uint8("game_save.skill.skill_id", "skill_id", base.DEC, nil, nil)
+hf.skill_points = ProtoField.uint8("game_save.skill.points", "points", base.DEC, nil, nil)
+hf.skill_multiplier = ProtoField.float("game_save.skill.multiplier", "multiplier")
+hf.skill_modifiers = ProtoField.bool("game_save.skill.modifiers", "modifiers", base.NONE, nil, nil)
+hf.inventory_max_slots = ProtoField.uint8("game_save.inventory.max_slots", "max_slots", base.DEC, nil, nil)
+hf.options = ProtoField.bool("game_save.options", "options", base.NONE, nil, nil)
+proto.fields = {
+    hf.version,
+    hf.play_time,
+    hf.player_count,
+    hf.player_player_id,
+    hf.player_level,
+    hf.skill_skill_id,
+    hf.skill_points,
+    hf.skill_multiplier,
+    hf.skill_modifiers,
+    hf.inventory_max_slots,
+    hf.options,
+}
+
+-- Reads `width` bits at bit offset `bit` as an unsigned integer, least significant bits first.
+local function read(tvb, bit, width)
+    local value = 0
+    local shift = 0
+    while shift < width do
+        local at = bit % 8
+        local take = math.min(8 - at, width - shift)
+        local byte = tvb(bit // 8, 1):uint()
+        value = value | ((byte >> at) & ((1 << take) - 1)) << shift
+        bit = bit + take
+        shift = shift + take
+    end
+    return value
+end
+
+local function signed(value, width)
+    if width < 64 and value >= 1 << (width - 1) then
+        return value - (1 << width)
+    end
+    return value
+end
+
+local function float(raw, width)
+    if width == 16 then
+        local sign = raw >> 15 == 1 and -1 or 1
+        local exponent = raw >> 10 & 0x1f
+        local fraction = raw & 0x3ff
+        if exponent == 0 then
+            return sign * fraction * 2^-24
+        elseif exponent == 31 then
+            return fraction == 0 and sign * math.huge or 0 / 0
+        end
+        return sign * (1 + fraction / 1024) * 2^(exponent - 15)
+    end
+    if width == 32 then
+        return (string.unpack("<f", string.pack("<I4", raw)))
+    end
+    return (string.unpack("<d", string.pack("<i8", raw)))
+end
+
+-- The bytes covering `width` bits at bit offset `bit`.
+local function span(tvb, bit, width)
+    return tvb(bit // 8, (bit % 8 + width + 7) // 8)
+end
+
+-- Rounds `bit` up to a multiple of `to` bits past `first`.
+local function align(bit, first, to)
+    return first + (bit - first + to - 1) // to * to
+end
+
+local dissect_game_save, dissect_player_unaligned, dissect_skill_unaligned, dissect_inventory_unaligned
+
+function dissect_skill_unaligned(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.skill_id = read(tvb, bit, 8)
+    tree:add(hf.skill_skill_id, span(tvb, bit, 8), v.skill_id)
+    bit = bit + 8
+    v.points = read(tvb, bit, 8)
+    tree:add(hf.skill_points, span(tvb, bit, 8), v.points)
+    bit = bit + 8
+    v.multiplier = float(read(tvb, bit, 32), 32)
+    tree:add(hf.skill_multiplier, span(tvb, bit, 32), v.multiplier)
+    bit = bit + 32
+    do
+        local field_start = bit
+        do
+            local list0 = {}
+            local sub0 = tree:add(tvb(bit // 8, 0), "modifiers")
+            local first0 = bit
+            for i0 = 1, 2 do
+                list0[i0] = read(tvb, bit, 1) == 1
+                sub0:add(hf.skill_modifiers, span(tvb, bit, 1), list0[i0])
+                bit = bit + 1
+            end
+            sub0:set_len((bit + 7) // 8 - first0 // 8)
+            v.modifiers = list0
+        end
+        bit = align(bit, field_start, 8)
+    end
+    return bit, v
+end
+
+function dissect_inventory_unaligned(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.max_slots = read(tvb, bit, 8)
+    tree:add(hf.inventory_max_slots, span(tvb, bit, 8), v.max_slots)
+    bit = bit + 8
+    error("items: count from `get_actual_slots()` cannot be computed here")
+    return bit, v
+end
+
+function dissect_player_unaligned(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.player_id = read(tvb, bit, 32)
+    tree:add(hf.player_player_id, span(tvb, bit, 32), v.player_id)
+    bit = bit + 32
+    v.level = read(tvb, bit, 8)
+    tree:add(hf.player_level, span(tvb, bit, 8), v.level)
+    bit = bit + 8
+    do
+        local list0 = {}
+        local sub0 = tree:add(tvb(bit // 8, 0), "skills")
+        local first0 = bit
+        for i0 = 1, v.level do
+            do
+                local sub = sub0:add(tvb(bit // 8, 0), "[" .. (i0 - 1) .. "]")
+                local first = bit
+                bit, list0[i0] = dissect_skill_unaligned(tvb, sub, bit)
+                sub:set_len((bit + 7) // 8 - first // 8)
+            end
+        end
+        sub0:set_len((bit + 7) // 8 - first0 // 8)
+        v.skills = list0
+    end
+    do
+        local sub = tree:add(tvb(bit // 8, 0), "inventory")
+        local first = bit
+        bit, v.inventory = dissect_inventory_unaligned(tvb, sub, bit)
+        sub:set_len((bit + 7) // 8 - first // 8)
+    end
+    return bit, v
+end
+
+function dissect_game_save(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.version = read(tvb, bit, 16)
+    tree:add_le(hf.version, tvb(bit // 8, 2))
+    bit = bit + 16
+    if v.version >= 2 then
+        v.play_time = read(tvb, bit, 32)
+        tree:add_le(hf.play_time, tvb(bit // 8, 4))
+        bit = bit + 32
+    end
+    v.player_count = read(tvb, bit, 8)
+    tree:add_le(hf.player_count, tvb(bit // 8, 1))
+    bit = bit + 8
+    do
+        local list0 = {}
+        local sub0 = tree:add(tvb(bit // 8, 0), "players")
+        local first0 = bit
+        for i0 = 1, v.player_count do
+            do
+                local sub = sub0:add(tvb(bit // 8, 0), "[" .. (i0 - 1) .. "]")
+                local first = bit
+                bit, list0[i0] = dissect_player_unaligned(tvb, sub, bit)
+                sub:set_len((bit + 7) // 8 - first // 8)
+            end
+        end
+        sub0:set_len((bit + 7) // 8 - first0 // 8)
+        v.players = list0
+    end
+    do
+        local field_start = bit
+        do
+            local list0 = {}
+            local sub0 = tree:add(tvb(bit // 8, 0), "options")
+            local first0 = bit
+            for i0 = 1, 6 do
+                list0[i0] = read(tvb, bit, 1) == 1
+                sub0:add(hf.options, span(tvb, bit, 1), list0[i0])
+                bit = bit + 1
+            end
+            sub0:set_len((bit + 7) // 8 - first0 // 8)
+            v.options = list0
+        end
+        bit = align(bit, field_start, 8)
+    end
+    return bit, v
+end
+
+function proto.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = proto.name
+    local bit = dissect_game_save(tvb, tree:add(proto, tvb()), 0)
+    return (bit + 7) // 8
+end
+
+-- Pick the port under Decode As, or register one: DissectorTable.get("udp.port"):add(5000, proto)
+DissectorTable.get("udp.port"):add_for_decode_as(proto)
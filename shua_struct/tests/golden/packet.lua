@@ -0,0 +1,183 @@
+-- Wireshark dissector for `Packet`, generated by shua_struct.
+
+local proto = Proto("packet", "A packet header.")
+
+local hf = {}
+hf.version = ProtoField.uint8("packet.version", "version", base.DEC, nil, 0xe0)
+hf.urgent = ProtoField.bool("packet.urgent", "urgent", 8, nil, 0x10)
+hf.len = ProtoField.uint8("packet.len", "len", base.DEC, nil, nil)
+hf.points_at = ProtoField.uint8("packet.points_at", "points_at", base.DEC, nil, nil, "Where the points start.")
+hf.checksum = ProtoField.uint32("packet.checksum", "checksum", base.DEC, nil, nil)
+hf.point_x = ProtoField.int16("packet.point.x", "x", base.DEC, nil, nil)
+hf.point_y = ProtoField.uint8("packet.point.y", "y", base.DEC, nil, 0xf0)
+hf.point_x_2 = ProtoField.int24("packet.point.x", "x", base.DEC, nil, 0xffff0)
+hf.point_y_2 = ProtoField.uint8("packet.point.y", "y", base.DEC, nil, 0xf)
+hf.point_y_3 = ProtoField.uint8("packet.point.y", "y", base.DEC, nil, nil)
+proto.fields = {
+    hf.version,
+    hf.urgent,
+    hf.len,
+    hf.points_at,
+    hf.checksum,
+    hf.point_x,
+    hf.point_y,
+    hf.point_x_2,
+    hf.point_y_2,
+    hf.point_y_3,
+}
+
+-- Reads `width` bits at bit offset `bit` as an unsigned integer, most significant bits first.
+local function read(tvb, bit, width)
+    local value = 0
+    local remaining = width
+    while remaining > 0 do
+        local at = bit % 8
+        local take = math.min(8 - at, remaining)
+        local byte = tvb(bit // 8, 1):uint()
+        value = value << take | (byte >> (8 - at - take)) & ((1 << take) - 1)
+        bit = bit + take
+        remaining = remaining - take
+    end
+    return value
+end
+
+local function signed(value, width)
+    if width < 64 and value >= 1 << (width - 1) then
+        return value - (1 << width)
+    end
+    return value
+end
+
+local function float(raw, width)
+    if width == 16 then
+        local sign = raw >> 15 == 1 and -1 or 1
+        local exponent = raw >> 10 & 0x1f
+        local fraction = raw & 0x3ff
+        if exponent == 0 then
+            return sign * fraction * 2^-24
+        elseif exponent == 31 then
+            return fraction == 0 and sign * math.huge or 0 / 0
+        end
+        return sign * (1 + fraction / 1024) * 2^(exponent - 15)
+    end
+    if width == 32 then
+        return (string.unpack("<f", string.pack("<I4", raw)))
+    end
+    return (string.unpack("<d", string.pack("<i8", raw)))
+end
+
+-- The bytes covering `width` bits at bit offset `bit`.
+local function span(tvb, bit, width)
+    return tvb(bit // 8, (bit % 8 + width + 7) // 8)
+end
+
+-- Rounds `bit` up to a multiple of `to` bits past `first`.
+local function align(bit, first, to)
+    return first + (bit - first + to - 1) // to * to
+end
+
+local dissect_packet, dissect_point, dissect_point_at4, dissect_point_unaligned
+
+function dissect_point(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.x = signed(read(tvb, bit, 16), 16)
+    tree:add(hf.point_x, tvb(bit // 8, 2))
+    bit = bit + 16
+    v.y = read(tvb, bit, 4)
+    tree:add(hf.point_y, tvb(bit // 8, 1))
+    bit = bit + 4
+    return bit, v
+end
+
+function dissect_point_at4(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.x = signed(read(tvb, bit, 16), 16)
+    tree:add(hf.point_x_2, tvb(bit // 8, 3))
+    bit = bit + 16
+    v.y = read(tvb, bit, 4)
+    tree:add(hf.point_y_2, tvb(bit // 8, 1))
+    bit = bit + 4
+    return bit, v
+end
+
+function dissect_point_unaligned(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    v.x = signed(read(tvb, bit, 16), 16)
+    tree:add(hf.point_x, span(tvb, bit, 16), v.x)
+    bit = bit + 16
+    v.y = read(tvb, bit, 4)
+    tree:add(hf.point_y_3, span(tvb, bit, 4), v.y)
+    bit = bit + 4
+    return bit, v
+end
+
+-- A packet header.
+function dissect_packet(tvb, tree, bit)
+    local start = bit
+    local v = {}
+    local stop = bit
+    v.version = read(tvb, bit, 3)
+    tree:add(hf.version, tvb(bit // 8, 1))
+    bit = bit + 3
+    v.urgent = read(tvb, bit, 1) == 1
+    tree:add(hf.urgent, tvb(bit // 8, 1))
+    bit = bit + 1
+    bit = bit + 4
+    v.len = read(tvb, bit, 8)
+    tree:add(hf.len, tvb(bit // 8, 1))
+    bit = bit + 8
+    v.points_at = read(tvb, bit, 8)
+    tree:add(hf.points_at, tvb(bit // 8, 1))
+    bit = bit + 8
+    if v.len > 0 then
+        v.checksum = read(tvb, bit, 32)
+        tree:add(hf.checksum, tvb(bit // 8, 4))
+        bit = bit + 32
+    end
+    do
+        local sub = tree:add(tvb(bit // 8, 0), "origin")
+        local first = bit
+        bit, v.origin = dissect_point(tvb, sub, bit)
+        sub:set_len((bit + 7) // 8 - first // 8)
+    end
+    do
+        local sub = tree:add(tvb(bit // 8, 0), "end")
+        local first = bit
+        bit, v["end"] = dissect_point_at4(tvb, sub, bit)
+        sub:set_len((bit + 7) // 8 - first // 8)
+    end
+    do
+        local saved = bit
+        bit = start + (v.points_at) * 8
+        do
+            local list0 = {}
+            local sub0 = tree:add(tvb(bit // 8, 0), "points")
+            local first0 = bit
+            for i0 = 1, v.len do
+                do
+                    local sub = sub0:add(tvb(bit // 8, 0), "[" .. (i0 - 1) .. "]")
+                    local first = bit
+                    bit, list0[i0] = dissect_point_unaligned(tvb, sub, bit)
+                    sub:set_len((bit + 7) // 8 - first // 8)
+                end
+            end
+            sub0:set_len((bit + 7) // 8 - first0 // 8)
+            v.points = list0
+        end
+        stop = math.max(stop, bit)
+        bit = saved
+    end
+    return math.max(bit, stop), v
+end
+
+function proto.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = proto.name
+    local bit = dissect_packet(tvb, tree:add(proto, tvb()), 0)
+    return (bit + 7) // 8
+end
+
+-- Pick the port under Decode As, or register one: DissectorTable.get("udp.port"):add(5000, proto)
+DissectorTable.get("udp.port"):add_for_decode_as(proto)
@@ -0,0 +1,35 @@
+/* C declarations for `Record`, generated by shua_struct. */
+
+#ifndef RECORD_H
+#define RECORD_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#if defined(__BYTE_ORDER__) && __BYTE_ORDER__ != __ORDER_LITTLE_ENDIAN__
+#error "these structs are laid out for little-endian targets"
+#endif
+
+struct Point {
+    uint16_t x;
+    uint16_t y;
+};
+_Static_assert(sizeof(struct Point) == 4, "struct Point must be 4 bytes");
+
+/* A telemetry record shared with C code. */
+struct Record {
+    uint8_t kind;
+    uint8_t _pad0[3];
+    uint32_t id;
+    uint8_t level : 3;
+    uint8_t channel : 4;
+    bool enabled : 1;
+    uint8_t _pad1[1];
+    struct Point origin;
+    uint16_t seq;
+    uint8_t tail;
+    uint8_t _pad2[3];
+};
+_Static_assert(sizeof(struct Record) == 20, "struct Record must be 20 bytes");
+
+#endif /* RECORD_H */
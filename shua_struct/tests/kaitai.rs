@@ -0,0 +1,70 @@
+#![cfg(all(
+    feature = "array",
+    feature = "bool",
+    feature = "int",
+    feature = "float"
+))]
+
+mod common;
+
+use common::{GameSave, golden};
+use shua_struct::kaitai::ksy;
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Lsb0};
+
+/// A packet header.
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Packet {
+    #[binary_field(bits = 3)]
+    pub version: u8,
+    #[binary_field(pad_bits_after = 4)]
+    pub urgent: bool,
+    pub len: u16,
+    #[binary_field(cond = "self.len > 0")]
+    pub checksum: u32,
+    pub body_at: u8,
+    #[binary_field(size_field = len, align = 32)]
+    pub words: Vec<u8>,
+    #[binary_field(offset_field = body_at)]
+    pub body: [u8; 2],
+}
+
+#[test]
+fn game_save() {
+    golden("game_save.ksy", &ksy(&GameSave::schema()));
+}
+
+#[test]
+fn packet() {
+    golden("packet.ksy", &ksy(&Packet::schema()));
+}
+
+/// A directory entry whose name is stored after the entry table.
+#[derive(Default, BinaryStruct)]
+pub struct Entry {
+    pub name_at: u8,
+    pub len: u8,
+    #[binary_field(offset_field = name_at)]
+    pub name: [u8; 2],
+    #[binary_field(seek = "self.len + 1", relative_to = "current")]
+    pub tail: u8,
+}
+
+/// A directory of entries.
+#[derive(Default, BinaryStruct)]
+pub struct Directory {
+    pub count: u8,
+    pub first: Entry,
+}
+
+#[test]
+fn nested_offsets() {
+    golden("directory.ksy", &ksy(&Directory::schema()));
+
+    // Both offsets count from inside `first`, which starts at byte 1: `name` at
+    // 1 + 4 and `tail` after `len`, at 3 + (1 + 1).
+    let bytes = [1, 4, 1, 0xEE, 0xFF, b'a', b'b'];
+    let (dir, _) = Directory::parse(BitSlice::<u8, Lsb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(dir.first.name, *b"ab");
+    assert_eq!(dir.first.tail, b'a');
+}
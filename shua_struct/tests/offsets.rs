@@ -0,0 +1,32 @@
+#![cfg(feature = "int")]
+
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Lsb0};
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Packed {
+    #[binary_field(bits = 12)]
+    pub offset: u16,
+    #[binary_field(bits = 4)]
+    pub kind: u8,
+    pub gap: u8,
+    #[binary_field(offset_field = offset)]
+    pub payload: u8,
+}
+
+#[test]
+fn backfill_keeps_the_offset_width() {
+    let packed = Packed {
+        offset: 0,
+        kind: 0xA,
+        gap: 0x11,
+        payload: 0x22,
+    };
+    let bytes = packed.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0x03, 0xA0, 0x11, 0x22]);
+
+    let (parsed, _) = Packed::parse(BitSlice::<u8, Lsb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(parsed.offset, 3);
+    assert_eq!(parsed.kind, 0xA);
+    assert_eq!(parsed.payload, 0x22);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "array")]
+
+use shua_struct::{BinaryField, BitSlice, Lsb0, Options};
+
+fn opts(sizes: &[usize]) -> Option<Options> {
+    Some(Options {
+        sizes: sizes.to_vec(),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn size_skips_placeholders() {
+    assert_eq!(opts(&[3]).unwrap().size(), Some(3));
+    assert_eq!(opts(&[0, 3]).unwrap().size(), None);
+    assert_eq!(opts(&[0, 3]).unwrap().child().unwrap().size(), Some(3));
+}
+
+#[test]
+fn placeholder_level_is_missing_a_size() {
+    let bits = BitSlice::<u8, Lsb0>::from_slice(&[1, 2, 3, 4]);
+    let err = <Vec<u8> as BinaryField<Lsb0>>::parse(bits, &opts(&[0])).unwrap_err();
+    assert!(err.to_string().contains("missing size"), "{}", err);
+    let err = <Vec<Vec<u8>> as BinaryField<Lsb0>>::parse(bits, &opts(&[2, 0])).unwrap_err();
+    assert!(err.to_string().contains("missing size"), "{}", err);
+
+    let (value, len) = <Vec<Vec<u8>> as BinaryField<Lsb0>>::parse(bits, &opts(&[2, 2])).unwrap();
+    assert_eq!(value, [[1, 2], [3, 4]]);
+    assert_eq!(len, 32);
+}
+
+#[test]
+fn count_of_zero_is_empty() {
+    let bits = BitSlice::<u8, Lsb0>::from_slice(&[1, 2]);
+    let opts = Some(Options {
+        sizes: vec![0, 2],
+        count: Some(0),
+        ..Default::default()
+    });
+    let (value, len) = <Vec<Vec<u8>> as BinaryField<Lsb0>>::parse(bits, &opts).unwrap();
+    assert!(value.is_empty());
+    assert_eq!(len, 0);
+}
+
+#[cfg(feature = "int")]
+#[test]
+fn size_field_of_zero_is_empty() {
+    #[derive(Debug, Default, PartialEq, shua_struct::BinaryStruct)]
+    pub struct Counted {
+        pub len: u8,
+        #[binary_field(size_field = len)]
+        pub items: Vec<u16>,
+        pub tail: u8,
+    }
+
+    let bits = BitSlice::<u8, Lsb0>::from_slice(&[0, 9]);
+    let (counted, len) = Counted::parse(bits, &None).unwrap();
+    assert_eq!(counted.items, []);
+    assert_eq!(counted.tail, 9);
+    assert_eq!(len, 16);
+    assert_eq!(counted.build(&None).unwrap().into_vec(), [0, 9]);
+}
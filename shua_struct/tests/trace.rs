@@ -0,0 +1,79 @@
+#![cfg(all(feature = "array", feature = "tuple", feature = "int"))]
+
+use shua_struct::{BinaryStruct, BitSlice, Lsb0, Span};
+
+#[derive(Debug, Default, Clone, Copy, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Point {
+    pub x: u8,
+    #[binary_field(bits = 4)]
+    pub y: u8,
+    #[binary_field(bits = 4)]
+    pub z: u8,
+}
+
+#[derive(Debug, Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Frame {
+    pub tag: u8,
+    pub origin: Point,
+    pub pair: (u8, u16),
+    pub samples: [Point; 2],
+}
+
+const BYTES: [u8; 10] = [0x01, 0x02, 0x43, 0x04, 0x05, 0x06, 0x07, 0x98, 0x09, 0xBA];
+
+fn spans(span: &Span<Lsb0>) -> Vec<(&str, usize, usize, &str)> {
+    span.iter()
+        .map(|span| {
+            (
+                span.path.as_str(),
+                span.start_bit,
+                span.len_bits,
+                span.value.as_str(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn nested_spans_have_paths_and_bit_offsets() {
+    let (frame, span) = Span::<Lsb0>::trace::<Frame>(BitSlice::from_slice(&BYTES), &None);
+    assert_eq!(frame.unwrap().samples[1].z, 0xB);
+    assert_eq!(
+        spans(&span),
+        [
+            ("", 0, 80, ""),
+            ("tag", 0, 8, "1"),
+            ("origin", 8, 16, ""),
+            ("origin.x", 8, 8, "2"),
+            ("origin.y", 16, 4, "3"),
+            ("origin.z", 20, 4, "4"),
+            ("pair", 24, 24, ""),
+            ("pair.0", 24, 8, "4"),
+            ("pair.1", 32, 16, "1541"),
+            ("samples", 48, 32, ""),
+            ("samples[0]", 48, 16, ""),
+            ("samples[0].x", 48, 8, "7"),
+            ("samples[0].y", 56, 4, "8"),
+            ("samples[0].z", 60, 4, "9"),
+            ("samples[1]", 64, 16, ""),
+            ("samples[1].x", 64, 8, "9"),
+            ("samples[1].y", 72, 4, "10"),
+            ("samples[1].z", 76, 4, "11"),
+        ]
+    );
+    assert_eq!(span.at_bit(61).unwrap().path, "samples[0].z");
+    assert_eq!(span.get("pair.1").unwrap().raw.as_raw_slice(), [0x05, 0x06]);
+}
+
+#[test]
+fn failed_spans_show_how_far_parsing_got() {
+    let (frame, span) = Span::<Lsb0>::trace::<Frame>(BitSlice::from_slice(&BYTES[..7]), &None);
+    assert!(frame.is_err());
+    assert!(span.failed);
+    assert!(span.get("samples").unwrap().failed);
+    assert!(span.get("samples[0]").unwrap().failed);
+    assert_eq!(span.get("samples[0].x").unwrap().value, "7");
+    assert_eq!(span.get("pair").unwrap().len_bits, 24);
+}
@@ -0,0 +1,17 @@
+use shua_struct::BinaryStruct;
+
+#[derive(Default, BinaryStruct)]
+pub struct Record {
+    pub count: u8,
+    #[binary_field(build_with = build_items)]
+    pub items: [u8; 4],
+}
+
+fn build_items(
+    items: &[u8; 4],
+) -> Result<shua_struct::BitVec<u8, shua_struct::Lsb0>, shua_struct::Error> {
+    let _ = items;
+    Ok(shua_struct::BitVec::new())
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use shua_struct::BinaryStruct;
+
+#[derive(Default, BinaryStruct)]
+pub struct Record {
+    #[binary_field(calc = "4")]
+    pub offset: u8,
+    #[binary_field(offset_field = offset)]
+    pub payload: u8,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use shua_struct::BinaryStruct;
+
+#[derive(Default, BinaryStruct)]
+pub struct Record {
+    #[binary_field(wire = u8, map = "|x: u8| x as u16", unmap = "|x: &u16| *x as u8")]
+    pub offset: u16,
+    #[binary_field(offset_field = offset)]
+    pub payload: u8,
+}
+
+fn main() {}
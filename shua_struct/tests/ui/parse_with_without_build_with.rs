@@ -0,0 +1,17 @@
+use shua_struct::BinaryStruct;
+
+#[derive(Default, BinaryStruct)]
+pub struct Record {
+    pub count: u8,
+    #[binary_field(parse_with = parse_items)]
+    pub items: [u8; 4],
+}
+
+fn parse_items(
+    bits: &shua_struct::BitSlice<u8, shua_struct::Lsb0>,
+) -> Result<([u8; 4], usize), shua_struct::Error> {
+    let _ = bits;
+    Ok(([0; 4], 32))
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use shua_struct::BinaryStruct;
+
+#[derive(Default, BinaryStruct)]
+pub struct Record {
+    pub count: u8,
+    #[binary_field(verify_calc)]
+    pub items: [u8; 4],
+}
+
+fn main() {}
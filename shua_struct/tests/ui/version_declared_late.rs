@@ -0,0 +1,11 @@
+use shua_struct::BinaryStruct;
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(version = version)]
+pub struct Record {
+    #[binary_field(since = 2)]
+    pub extra: u8,
+    pub version: u8,
+}
+
+fn main() {}
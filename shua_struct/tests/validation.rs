@@ -0,0 +1,99 @@
+#![cfg(feature = "int")]
+
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Error, Lsb0};
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Header {
+    pub version: u8,
+    #[binary_field(assert = "self.kind < 4", msg = "unknown kind")]
+    pub kind: u8,
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Frame {
+    pub sync: u16,
+    pub header: Header,
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+#[binary_struct(validate = check, validate_on_build)]
+pub struct Range {
+    pub lo: u8,
+    pub hi: u8,
+}
+
+impl Range {
+    fn check(&self) -> Result<(), &'static str> {
+        if self.lo <= self.hi {
+            Ok(())
+        } else {
+            Err("lo exceeds hi")
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Window {
+    pub id: u8,
+    pub range: Range,
+}
+
+fn error(path: &str, bit: usize, message: &str) -> Error {
+    Error {
+        path: path.into(),
+        bit,
+        message: message.into(),
+    }
+}
+
+#[test]
+fn nested_assert_locates_the_field() {
+    let bytes = [0x34, 0x12, 1, 9];
+    let err = Frame::parse(BitSlice::<u8, Lsb0>::from_slice(&bytes), &None).unwrap_err();
+    assert_eq!(err, error("header.kind", 24, "unknown kind"));
+
+    let bytes = [0x34, 0x12, 1, 3];
+    let (frame, _) = Frame::parse(BitSlice::<u8, Lsb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(frame.header.kind, 3);
+}
+
+#[test]
+fn assert_without_msg_names_the_expression() {
+    #[derive(Debug, Default, BinaryStruct)]
+    pub struct Small {
+        #[binary_field(assert = "self.n < 2")]
+        pub n: u8,
+    }
+
+    let err = Small::parse(BitSlice::<u8, Lsb0>::from_slice(&[5]), &None).unwrap_err();
+    assert_eq!(err.path, "n");
+    assert_eq!(err.bit, 0);
+    assert!(err.message.starts_with("assertion failed: "), "{}", err);
+}
+
+#[test]
+fn validate_runs_on_parse() {
+    let err = Window::parse(BitSlice::<u8, Lsb0>::from_slice(&[7, 5, 2]), &None).unwrap_err();
+    assert_eq!(err, error("range", 8, "lo exceeds hi"));
+
+    let (window, _) = Window::parse(BitSlice::<u8, Lsb0>::from_slice(&[7, 2, 5]), &None).unwrap();
+    assert_eq!(window.range, Range { lo: 2, hi: 5 });
+}
+
+#[test]
+fn validate_on_build_fails_the_build() {
+    let window = Window {
+        id: 7,
+        range: Range { lo: 5, hi: 2 },
+    };
+    assert_eq!(
+        window.build(&None).unwrap_err(),
+        error("range", 8, "lo exceeds hi")
+    );
+
+    let window = Window {
+        id: 7,
+        range: Range { lo: 2, hi: 5 },
+    };
+    assert_eq!(window.build(&None).unwrap().into_vec(), [7, 2, 5]);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "int")]
+
+use shua_struct::{BinaryField, BinaryStruct, BitSlice, Error, Lsb0};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Kind {
+    #[default]
+    Data,
+    Ack,
+}
+
+impl TryFrom<u8> for Kind {
+    type Error = String;
+
+    fn try_from(raw: u8) -> Result<Self, String> {
+        match raw {
+            0 => Ok(Kind::Data),
+            1 => Ok(Kind::Ack),
+            _ => Err(format!("unknown kind {}", raw)),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Header {
+    pub seq: u8,
+    #[binary_field(wire = u8, try_map = "Kind::try_from", unmap = "|k: &Kind| *k as u8")]
+    pub kind: Kind,
+    /// Tenths of a degree on the wire, which must fit a `u8`.
+    #[binary_field(
+        wire = u8,
+        map = "|t: u8| f32::from(t) / 10.0",
+        try_unmap = "|t: &f32| u8::try_from((*t * 10.0).round() as i32)"
+    )]
+    pub temp: f32,
+}
+
+#[derive(Debug, Default, PartialEq, BinaryStruct)]
+pub struct Packet {
+    pub len: u16,
+    pub header: Header,
+}
+
+fn parse(bytes: &[u8]) -> Result<Packet, Error> {
+    Packet::parse(BitSlice::<u8, Lsb0>::from_slice(bytes), &None).map(|(packet, _)| packet)
+}
+
+#[test]
+fn map_and_unmap_round_trip() {
+    let bytes = [3, 0, 9, 1, 215];
+    let packet = parse(&bytes).unwrap();
+    assert_eq!(
+        packet.header,
+        Header {
+            seq: 9,
+            kind: Kind::Ack,
+            temp: 21.5,
+        }
+    );
+    assert_eq!(packet.build(&None).unwrap().into_vec(), bytes);
+}
+
+#[test]
+fn try_map_failure_names_the_field() {
+    let err = parse(&[3, 0, 9, 7, 215]).unwrap_err();
+    assert_eq!(
+        err,
+        Error {
+            path: "header.kind".into(),
+            bit: 24,
+            message: "unknown kind 7".into(),
+        }
+    );
+}
+
+#[test]
+fn try_unmap_failure_fails_the_build() {
+    let packet = Packet {
+        len: 3,
+        header: Header {
+            seq: 9,
+            kind: Kind::Data,
+            temp: 30.0,
+        },
+    };
+    let err = packet.build(&None).unwrap_err();
+    assert_eq!(err.path, "header.temp");
+    assert_eq!(err.bit, 32);
+    assert_eq!(
+        err.message,
+        "out of range integral type conversion attempted"
+    );
+
+    let packet = Packet {
+        header: Header {
+            temp: 25.5,
+            ..packet.header
+        },
+        ..packet
+    };
+    assert_eq!(packet.build(&None).unwrap().into_vec(), [3, 0, 9, 0, 255]);
+}
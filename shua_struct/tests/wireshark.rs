@@ -0,0 +1,58 @@
+#![cfg(all(
+    feature = "array",
+    feature = "bool",
+    feature = "int",
+    feature = "float"
+))]
+
+mod common;
+
+use common::{GameSave, golden};
+use shua_struct::BinaryStruct;
+use shua_struct::wireshark::lua;
+
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Point {
+    pub x: i16,
+    #[binary_field(bits = 4)]
+    pub y: u8,
+}
+
+/// A packet header.
+#[derive(Default, BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Packet {
+    #[binary_field(bits = 3)]
+    pub version: u8,
+    #[binary_field(pad_bits_after = 4)]
+    pub urgent: bool,
+    pub len: u8,
+    /// Where the points start.
+    pub points_at: u8,
+    #[binary_field(cond = "self.len > 0")]
+    pub checksum: u32,
+    pub origin: Point,
+    pub end: Point,
+    #[binary_field(offset_field = points_at, size_field = len)]
+    pub points: Vec<Point>,
+}
+
+#[test]
+fn game_save() {
+    golden("game_save.lua", &lua(&GameSave::schema()));
+}
+
+#[test]
+fn packet() {
+    golden("packet.lua", &lua(&Packet::schema()));
+}
+
+#[test]
+fn dissectors_are_declared_before_they_are_defined() {
+    let script = lua(&Packet::schema());
+    let declared = script.find("local dissect_packet, ").unwrap();
+    let first = script.find("function dissect_").unwrap();
+    assert!(declared < first);
+    assert!(!script.contains("local function dissect_"));
+}
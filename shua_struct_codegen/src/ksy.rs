@@ -0,0 +1,654 @@
+//! The Kaitai Struct `.ksy` subset.
+
+use crate::{Error, FieldDef, StructDef};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use serde_yaml::{Mapping, Value};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RawType {
+    meta: Option<Meta>,
+    #[serde(default)]
+    doc: String,
+    #[serde(rename = "doc-ref")]
+    _doc_ref: Option<IgnoredAny>,
+    #[serde(default)]
+    seq: Vec<Attr>,
+    #[serde(default)]
+    instances: Mapping,
+    #[serde(default)]
+    types: Mapping,
+    /// Enums only name values; fields using them stay integers.
+    #[serde(rename = "enums")]
+    _enums: Option<IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Meta {
+    id: Option<String>,
+    endian: Option<Value>,
+    bit_endian: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Attr {
+    id: Option<String>,
+    #[serde(rename = "type")]
+    ty: Option<Value>,
+    size: Option<Value>,
+    repeat: Option<String>,
+    repeat_expr: Option<Value>,
+    #[serde(rename = "if")]
+    cond: Option<String>,
+    #[serde(default)]
+    doc: String,
+    #[serde(rename = "doc-ref")]
+    _doc_ref: Option<IgnoredAny>,
+    contents: Option<Value>,
+    valid: Option<Value>,
+    #[serde(rename = "enum")]
+    enum_name: Option<String>,
+    pos: Option<Value>,
+    value: Option<Value>,
+    #[serde(rename = "-orig-id")]
+    _orig_id: Option<IgnoredAny>,
+}
+
+/// Byte or bit order, as Kaitai spells it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endian {
+    Le,
+    Be,
+}
+
+impl Endian {
+    fn parse(text: &str, path: &str) -> Result<Self, Error> {
+        match text {
+            "le" => Ok(Endian::Le),
+            "be" => Ok(Endian::Be),
+            _ => Err(Error::at(path, format!("unknown endianness `{}`", text))),
+        }
+    }
+}
+
+/// A user type with its nested types moved out.
+struct UserType {
+    id: String,
+    /// The Rust struct name.
+    name: String,
+    doc: String,
+    meta: Option<Meta>,
+    seq: Vec<Attr>,
+    instances: Vec<(String, Attr)>,
+}
+
+/// The user types of a document, root first.
+struct Types {
+    list: Vec<UserType>,
+}
+
+impl Types {
+    fn collect(&mut self, id: &str, raw: RawType) -> Result<(), Error> {
+        let name = camel_case(id);
+        if self.list.iter().any(|known| known.name == name) {
+            return Err(Error::at(id, "type defined twice"));
+        }
+        let mut instances = Vec::new();
+        for (key, value) in raw.instances {
+            let key = key
+                .as_str()
+                .ok_or_else(|| Error::at(id, "instance ids must be strings"))?
+                .to_string();
+            let attr = serde_yaml::from_value(value)
+                .map_err(|err| Error::at(format!("{}.{}", id, key), err.to_string()))?;
+            instances.push((key, attr));
+        }
+        self.list.push(UserType {
+            id: id.to_string(),
+            name,
+            doc: raw.doc.trim_end().to_string(),
+            meta: raw.meta,
+            seq: raw.seq,
+            instances,
+        });
+        for (key, value) in raw.types {
+            let key = key
+                .as_str()
+                .ok_or_else(|| Error::at(id, "type ids must be strings"))?
+                .to_string();
+            let nested =
+                serde_yaml::from_value(value).map_err(|err| Error::at(&key, err.to_string()))?;
+            self.collect(&key, nested)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Option<&UserType> {
+        self.list.iter().find(|ty| ty.id == id)
+    }
+}
+
+pub(crate) fn parse(source: &str) -> Result<Vec<StructDef>, Error> {
+    let root: RawType = serde_yaml::from_str(source).map_err(|err| Error::new(err.to_string()))?;
+    let id = root
+        .meta
+        .as_ref()
+        .ok_or_else(|| Error::new("missing `meta` section"))?
+        .id
+        .clone()
+        .ok_or_else(|| Error::at("meta", "missing `id`"))?;
+    let mut types = Types { list: Vec::new() };
+    types.collect(&id, root)?;
+    let order = bit_order(&types)?;
+    let bit_order = match order {
+        Endian::Le => "Lsb0",
+        Endian::Be => "Msb0",
+    };
+
+    let mut structs = Vec::new();
+    for ty in &types.list {
+        let mut st = StructDef {
+            name: ty.name.clone(),
+            doc: ty.doc.clone(),
+            bit_order: bit_order.into(),
+            ..StructDef::default()
+        };
+        let mut phase = Some(0);
+        for (index, attr) in ty.seq.iter().enumerate() {
+            let field_id = match &attr.id {
+                Some(field_id) => field_id.clone(),
+                None => format!("unnamed{}", index),
+            };
+            let path = format!("{}.{}", ty.id, field_id);
+            if attr.pos.is_some() || attr.value.is_some() {
+                return Err(Error::at(
+                    path,
+                    "`pos` and `value` are only supported on instances",
+                ));
+            }
+            st.fields
+                .push(field(attr, field_id, &path, &types, &mut phase)?);
+        }
+        for (field_id, attr) in &ty.instances {
+            let path = format!("{}.{}", ty.id, field_id);
+            if attr.value.is_some() {
+                // Computed from other fields, so not on the wire.
+                continue;
+            }
+            let Some(pos) = &attr.pos else {
+                return Err(Error::at(path, "instances need `pos` or `value`"));
+            };
+            let pos = expr_text(pos, &path)?;
+            // Placed fields start on a byte boundary and do not move the sequence.
+            let mut inner = Some(0);
+            let mut def = field(attr, field_id.clone(), &path, &types, &mut inner)?;
+            if is_ident(&pos) {
+                def.options.push(format!("offset_field = {}", pos));
+            } else {
+                def.options.push(format!("seek = {:?}", expr(&pos, &path)?));
+            }
+            st.fields.push(def);
+        }
+        structs.push(st);
+    }
+    Ok(structs)
+}
+
+/// The one bit order the document's byte and bit fields agree on.
+fn bit_order(types: &Types) -> Result<Endian, Error> {
+    let root = types.list[0].meta.as_ref();
+    let endian_text = |meta: Option<&Meta>| match meta.and_then(|meta| meta.endian.as_ref()) {
+        None => Ok(None),
+        Some(Value::String(endian)) => Ok(Some(endian.clone())),
+        Some(_) => Err(Error::at(
+            "meta.endian",
+            "switched endianness is not supported",
+        )),
+    };
+    let root_endian = endian_text(root)?;
+    let root_bit_endian = root.and_then(|meta| meta.bit_endian.clone());
+    let endian = root_endian
+        .as_deref()
+        .map(|endian| Endian::parse(endian, "meta.endian"))
+        .transpose()?;
+    // Byte orders of the multi-byte numbers, from suffixes or the default `endian`.
+    let mut byte_orders = Vec::new();
+    let mut bit_fields = false;
+    for ty in &types.list {
+        if let Some(meta) = &ty.meta {
+            let nested = endian_text(Some(meta))?;
+            if nested.is_some() && nested != root_endian
+                || meta.bit_endian.is_some() && meta.bit_endian != root_bit_endian
+            {
+                return Err(Error::at(
+                    format!("{}.meta", ty.id),
+                    "types with their own endianness are not supported",
+                ));
+            }
+        }
+        let attrs = ty
+            .seq
+            .iter()
+            .chain(ty.instances.iter().map(|(_, attr)| attr));
+        for attr in attrs {
+            let Some(Value::String(name)) = &attr.ty else {
+                continue;
+            };
+            if bits_type(name).is_some() {
+                bit_fields = true;
+                continue;
+            }
+            let (base, suffix) = split_suffix(name);
+            if !matches!(base, "u2" | "u4" | "u8" | "s2" | "s4" | "s8" | "f4" | "f8") {
+                continue;
+            }
+            match suffix.or(endian) {
+                Some(order) => byte_orders.push(order),
+                None => {
+                    return Err(Error::at(
+                        format!("{}.{}", ty.id, attr.id.as_deref().unwrap_or_default()),
+                        format!("`{}` needs `meta.endian` or an `le`/`be` suffix", name),
+                    ));
+                }
+            }
+        }
+    }
+    if byte_orders.windows(2).any(|pair| pair[0] != pair[1]) {
+        return Err(Error::at(
+            "meta",
+            "mixed byte orders are not supported; shua_struct has one bit order per struct",
+        ));
+    }
+    // Kaitai reads bit fields big-endian unless told otherwise.
+    let bit_endian = match &root_bit_endian {
+        Some(bit_endian) => Endian::parse(bit_endian, "meta.bit-endian")?,
+        None => Endian::Be,
+    };
+    match (byte_orders.first(), bit_fields) {
+        (Some(&bytes), true) if bytes != bit_endian => Err(Error::at(
+            "meta",
+            "byte order and bit order differ, but shua_struct has one bit order per struct; set `bit-endian` to match",
+        )),
+        (Some(&bytes), _) => Ok(bytes),
+        (None, true) => Ok(bit_endian),
+        (None, false) => Ok(endian.unwrap_or(Endian::Le)),
+    }
+}
+
+/// Splits an `le`/`be` suffix off a number type.
+fn split_suffix(ty: &str) -> (&str, Option<Endian>) {
+    if let Some(base) = ty.strip_suffix("le") {
+        (base, Some(Endian::Le))
+    } else if let Some(base) = ty.strip_suffix("be") {
+        (base, Some(Endian::Be))
+    } else {
+        (ty, None)
+    }
+}
+
+fn field(
+    attr: &Attr,
+    id: String,
+    path: &str,
+    types: &Types,
+    phase: &mut Option<usize>,
+) -> Result<FieldDef, Error> {
+    let mut def = FieldDef {
+        name: id,
+        doc: attr.doc.trim_end().to_string(),
+        ..FieldDef::default()
+    };
+
+    // The value's type, its width in bits if fixed, and whether it is read bitwise.
+    let (ty, width, bitwise) = match (&attr.ty, &attr.size, &attr.contents) {
+        (Some(Value::String(ty)), None, None) => {
+            if let Some(bits) = bits_type(ty) {
+                if bits > 1 {
+                    def.options.push(format!("bits = {}", bits));
+                }
+                (uint_for(bits).to_string(), Some(bits), true)
+            } else if let Some((rust, bits)) = byte_type(ty) {
+                (rust.to_string(), Some(bits), false)
+            } else if let Some(user) = types.get(ty) {
+                (user.name.clone(), type_width(user, types, 0), false)
+            } else {
+                return Err(Error::at(path, format!("unknown type `{}`", ty)));
+            }
+        }
+        (Some(Value::Mapping(_)), ..) => {
+            return Err(Error::at(path, "switch types are not supported"));
+        }
+        (Some(_), Some(_), _) => {
+            return Err(Error::at(
+                path,
+                "`size` together with `type` is not supported",
+            ));
+        }
+        (None, Some(size), None) => {
+            let size = expr_text(size, path)?;
+            match size.parse::<usize>() {
+                Ok(size) => (format!("[u8; {}]", size), Some(size * 8), false),
+                Err(_) if is_ident(&size) => {
+                    def.options.push(format!("size_field = {}", size));
+                    ("Vec<u8>".to_string(), None, false)
+                }
+                Err(_) => {
+                    return Err(Error::at(path, "`size` must be a number or a field name"));
+                }
+            }
+        }
+        (None, None, Some(contents)) => {
+            let bytes = contents_bytes(contents, path)?;
+            let list: Vec<String> = bytes.iter().map(u8::to_string).collect();
+            def.options.push(format!(
+                "assert = {:?}",
+                format!("self.{} == [{}]", def.name, list.join(", "))
+            ));
+            (
+                format!("[u8; {}]", bytes.len()),
+                Some(bytes.len() * 8),
+                false,
+            )
+        }
+        _ => {
+            return Err(Error::at(
+                path,
+                "needs exactly one of `type`, `size` or `contents`",
+            ));
+        }
+    };
+
+    let sized = def
+        .options
+        .iter()
+        .any(|option| option.starts_with("size_field"));
+    let (ty, width) = match (attr.repeat.as_deref(), &attr.repeat_expr) {
+        (None, None) => (ty, width),
+        (Some(_), _) if sized => {
+            return Err(Error::at(
+                path,
+                "repeated values of data-dependent size are not supported",
+            ));
+        }
+        (Some("expr"), Some(count)) => {
+            let count = expr_text(count, path)?;
+            match count.parse::<usize>() {
+                // Arrays need `Copy` items, which structs do not derive.
+                Ok(count) if types.get(&ty_id(attr)).is_some() => {
+                    def.options.push(format!("sizes = [{}]", count));
+                    (format!("Vec<{}>", ty), width.map(|width| width * count))
+                }
+                Ok(count) => (
+                    format!("[{}; {}]", ty, count),
+                    width.map(|width| width * count),
+                ),
+                Err(_) if is_ident(&count) => {
+                    def.options.push(format!("size_field = {}", count));
+                    (format!("Vec<{}>", ty), None)
+                }
+                Err(_) => {
+                    return Err(Error::at(
+                        path,
+                        "`repeat-expr` must be a number or a field name",
+                    ));
+                }
+            }
+        }
+        (Some(repeat), _) if repeat != "expr" => {
+            return Err(Error::at(
+                path,
+                format!("`repeat: {}` is not supported", repeat),
+            ));
+        }
+        _ => return Err(Error::at(path, "`repeat: expr` needs `repeat-expr`")),
+    };
+
+    // Kaitai moves to the next byte before anything that is not a bit field.
+    if !bitwise {
+        match *phase {
+            Some(0) => {}
+            Some(used) => def.options.insert(0, format!("pad_bits = {}", 8 - used)),
+            None => {
+                return Err(Error::at(
+                    path,
+                    "follows bit fields of data-dependent length, so its byte alignment is unknown",
+                ));
+            }
+        }
+        *phase = Some(0);
+    }
+    *phase = match (width, *phase) {
+        (Some(width), Some(used)) => Some((used + width) % 8),
+        // Byte-sized values keep the phase even if their count varies.
+        (None, Some(0)) if !bitwise => Some(0),
+        _ => None,
+    };
+
+    def.ty = ty;
+    if let Some(cond) = &attr.cond {
+        def.options.push(format!("cond = {:?}", expr(cond, path)?));
+    }
+    match &attr.valid {
+        None => {}
+        Some(Value::Mapping(valid)) if valid.len() == 1 && valid.contains_key("eq") => {
+            let value = expr_text(&valid["eq"], path)?;
+            def.options.push(format!(
+                "assert = {:?}",
+                format!("self.{} == {}", def.name, expr(&value, path)?)
+            ));
+        }
+        Some(value @ (Value::Number(_) | Value::Bool(_) | Value::String(_))) => {
+            let value = expr_text(value, path)?;
+            def.options.push(format!(
+                "assert = {:?}",
+                format!("self.{} == {}", def.name, expr(&value, path)?)
+            ));
+        }
+        Some(_) => {
+            return Err(Error::at(
+                path,
+                "only `valid` with a single value or `eq` is supported",
+            ));
+        }
+    }
+    if let Some(name) = &attr.enum_name {
+        if !def.doc.is_empty() {
+            def.doc.push_str("\n\n");
+        }
+        def.doc
+            .push_str(&format!("Values are from the `{}` enum.", name));
+    }
+    Ok(def)
+}
+
+/// The type name of an attribute, if it has one.
+fn ty_id(attr: &Attr) -> String {
+    match &attr.ty {
+        Some(Value::String(ty)) => ty.clone(),
+        _ => String::new(),
+    }
+}
+
+/// `bN`: the width of a bit-sized integer type.
+fn bits_type(ty: &str) -> Option<usize> {
+    let bits: usize = ty.strip_prefix('b')?.parse().ok()?;
+    (1..=64).contains(&bits).then_some(bits)
+}
+
+fn uint_for(bits: usize) -> &'static str {
+    match bits {
+        1 => "bool",
+        2..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        _ => "u64",
+    }
+}
+
+/// A byte-aligned number type and its width.
+///
+/// The document's byte order was checked against any `le`/`be` suffix up front.
+fn byte_type(ty: &str) -> Option<(&'static str, usize)> {
+    let rust = match split_suffix(ty).0 {
+        "u1" => ("u8", 8),
+        "u2" => ("u16", 16),
+        "u4" => ("u32", 32),
+        "u8" => ("u64", 64),
+        "s1" => ("i8", 8),
+        "s2" => ("i16", 16),
+        "s4" => ("i32", 32),
+        "s8" => ("i64", 64),
+        "f4" => ("f32", 32),
+        "f8" => ("f64", 64),
+        _ => return None,
+    };
+    Some(rust)
+}
+
+/// Width in bits of a user type, if fixed.
+fn type_width(ty: &UserType, types: &Types, depth: usize) -> Option<usize> {
+    if depth > 16 {
+        return None;
+    }
+    ty.seq.iter().try_fold(0, |total: usize, attr| {
+        if attr.cond.is_some() {
+            return None;
+        }
+        let width = match (&attr.ty, &attr.size, &attr.contents) {
+            (Some(Value::String(name)), None, None) => match bits_type(name) {
+                Some(bits) => bits,
+                None => match byte_type(name) {
+                    Some((_, bits)) => bits,
+                    None => type_width(types.get(name)?, types, depth + 1)?,
+                },
+            },
+            (None, Some(Value::Number(size)), None) => size.as_u64()? as usize * 8,
+            (None, None, Some(contents)) => contents_bytes(contents, "").ok()?.len() * 8,
+            _ => return None,
+        };
+        let count = match (&attr.repeat, &attr.repeat_expr) {
+            (None, _) => 1,
+            (Some(_), Some(Value::Number(count))) => count.as_u64()? as usize,
+            _ => return None,
+        };
+        Some(total + width * count)
+    })
+}
+
+/// The bytes of a `contents` value: a string, or a list of numbers and strings.
+fn contents_bytes(contents: &Value, path: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || {
+        Error::at(
+            path,
+            "`contents` must be a string or a list of bytes and strings",
+        )
+    };
+    match contents {
+        Value::String(text) => Ok(text.as_bytes().to_vec()),
+        Value::Sequence(items) => {
+            let mut bytes = Vec::new();
+            for item in items {
+                match item {
+                    Value::Number(number) => bytes.push(
+                        number
+                            .as_u64()
+                            .and_then(|byte| u8::try_from(byte).ok())
+                            .ok_or_else(invalid)?,
+                    ),
+                    Value::String(text) => bytes.extend_from_slice(text.as_bytes()),
+                    _ => return Err(invalid()),
+                }
+            }
+            Ok(bytes)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// A scalar YAML value as expression text.
+fn expr_text(value: &Value, path: &str) -> Result<String, Error> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(Error::at(path, "expected a number or an expression")),
+    }
+}
+
+/// Rewrites a Kaitai expression as a Rust expression over the struct's fields.
+fn expr(kaitai: &str, path: &str) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut chars = kaitai.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(at, next)) = chars.peek() {
+                if !(next.is_ascii_alphanumeric() || next == '_') {
+                    break;
+                }
+                end = at + next.len_utf8();
+                chars.next();
+            }
+            let word = &kaitai[start..end];
+            let member = out.trim_end().ends_with('.');
+            match word {
+                "and" => out.push_str("&&"),
+                "or" => out.push_str("||"),
+                "not" => {
+                    out.push('!');
+                    while chars.next_if(|(_, next)| next.is_whitespace()).is_some() {}
+                }
+                "true" | "false" => out.push_str(word),
+                "size" | "length" if member => out.push_str("len()"),
+                _ if word.starts_with('_') => {
+                    return Err(Error::at(
+                        path,
+                        format!("`{}` is not supported in expressions", word),
+                    ));
+                }
+                _ if member => out.push_str(word),
+                _ => {
+                    out.push_str("self.");
+                    out.push_str(word);
+                }
+            }
+        } else if c.is_ascii_digit() {
+            // Numbers, including suffix-free hex like `0x1f`, pass through whole.
+            out.push(c);
+            while let Some(&(_, next)) = chars.peek() {
+                if !(next.is_ascii_alphanumeric() || next == '_') {
+                    break;
+                }
+                out.push(next);
+                chars.next();
+            }
+        } else if c == '?' {
+            return Err(Error::at(path, "conditional expressions are not supported"));
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+fn is_ident(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `game_save` → `GameSave`.
+fn camel_case(id: &str) -> String {
+    id.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
@@ -0,0 +1,114 @@
+//! Generates `#[derive(BinaryStruct)]` definitions from data files, for use from `build.rs`.
+//!
+//! Two input formats are read:
+//!
+//! - a native TOML schema, whose field keys are the `#[binary_field(...)]` options
+//!   (see [`from_toml`]), and
+//! - a subset of Kaitai Struct `.ksy` (see [`from_ksy`]).
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("registers.rs");
+//!     shua_struct_codegen::generate("registers.toml", out).unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/registers.rs"));
+//! ```
+
+mod error;
+mod ksy;
+mod native;
+mod render;
+
+pub use error::Error;
+
+use std::path::Path;
+
+/// Generates Rust code from the schema at `input` into `output`.
+///
+/// The format is picked by extension: `.ksy` for Kaitai Struct, `.toml` for the
+/// native schema. Tells Cargo to rerun the build script when `input` changes.
+pub fn generate(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), Error> {
+    let input = input.as_ref();
+    println!("cargo:rerun-if-changed={}", input.display());
+    let source = std::fs::read_to_string(input)
+        .map_err(|err| Error::new(format!("cannot read {}: {}", input.display(), err)))?;
+    let code = match input.extension().and_then(|ext| ext.to_str()) {
+        Some("ksy") => from_ksy(&source)?,
+        Some("toml") => from_toml(&source)?,
+        _ => {
+            return Err(Error::new(format!(
+                "cannot tell the format of {}; expected a `.ksy` or `.toml` file",
+                input.display()
+            )));
+        }
+    };
+    let output = output.as_ref();
+    std::fs::write(output, code)
+        .map_err(|err| Error::new(format!("cannot write {}: {}", output.display(), err)))
+}
+
+/// Generates Rust code from a native TOML schema.
+///
+/// ```toml
+/// bit_order = "Msb0"        # default "Lsb0"
+/// derive = ["Debug"]        # extra derives, besides `Default` and `BinaryStruct`
+///
+/// [[struct]]
+/// name = "Status"
+/// doc = "Status register."
+///
+/// [[struct.field]]
+/// name = "ready"
+/// type = "bool"
+///
+/// [[struct.field]]
+/// name = "mode"
+/// type = "u8"
+/// bits = 3
+/// pad_bits_after = 4
+/// ```
+///
+/// Every field key besides `name`, `type` and `doc` is the `#[binary_field(...)]`
+/// option of the same name; structs take `bit_order`, `version` and `derive` to
+/// override the file-wide settings.
+pub fn from_toml(source: &str) -> Result<String, Error> {
+    render::render(&native::parse(source)?)
+}
+
+/// Generates Rust code from a Kaitai Struct `.ksy` document.
+///
+/// The supported subset is what maps onto the derive one to one: integer,
+/// float and `bN` types, user types from `types` (nested ones included), byte
+/// arrays with a fixed or field-given `size`, `repeat: expr` with a literal count
+/// or an earlier field, `if`, `contents`, and `pos` instances. `value` instances
+/// are skipped. Byte and bit order must agree, as there is one bit order per struct.
+pub fn from_ksy(source: &str) -> Result<String, Error> {
+    render::render(&ksy::parse(source)?)
+}
+
+/// A struct to generate.
+#[derive(Debug, Default)]
+pub(crate) struct StructDef {
+    pub name: String,
+    pub doc: String,
+    /// `Lsb0` or `Msb0`.
+    pub bit_order: String,
+    /// `version` option: a field name or an expression.
+    pub version: Option<String>,
+    /// Derives besides `Default` and `BinaryStruct`.
+    pub derives: Vec<String>,
+    pub fields: Vec<FieldDef>,
+}
+
+/// A field to generate.
+#[derive(Debug, Default)]
+pub(crate) struct FieldDef {
+    pub name: String,
+    pub ty: String,
+    pub doc: String,
+    /// `#[binary_field(...)]` options as written, e.g. `bits = 4`.
+    pub options: Vec<String>,
+}
@@ -0,0 +1,167 @@
+//! The native TOML schema.
+
+use crate::{Error, FieldDef, StructDef};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    bit_order: Option<String>,
+    #[serde(default)]
+    derive: Vec<String>,
+    #[serde(rename = "struct", default)]
+    structs: Vec<Struct>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Struct {
+    name: String,
+    #[serde(default)]
+    doc: String,
+    bit_order: Option<String>,
+    version: Option<String>,
+    derive: Option<Vec<String>>,
+    #[serde(rename = "field", default)]
+    fields: Vec<Field>,
+}
+
+/// A field; everything besides `name`, `type` and `doc` is a `#[binary_field(...)]` option.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default)]
+    doc: String,
+    bits: Option<usize>,
+    align: Option<usize>,
+    sub_align: Option<usize>,
+    sizes: Option<Vec<usize>>,
+    aligns: Option<Vec<usize>>,
+    size_field: Option<String>,
+    size_func: Option<String>,
+    pad_bits: Option<usize>,
+    pad_bytes: Option<usize>,
+    pad_bits_after: Option<usize>,
+    pad_bytes_after: Option<usize>,
+    cond: Option<String>,
+    since: Option<u64>,
+    until: Option<u64>,
+    #[serde(default)]
+    skip: bool,
+    default: Option<String>,
+    offset_field: Option<String>,
+    seek: Option<String>,
+    relative_to: Option<String>,
+    assert: Option<String>,
+    msg: Option<String>,
+    calc: Option<String>,
+    #[serde(default)]
+    verify_calc: bool,
+    ctx: Option<String>,
+}
+
+pub(crate) fn parse(source: &str) -> Result<Vec<StructDef>, Error> {
+    let file: File = toml::from_str(source).map_err(|err| Error::new(err.to_string()))?;
+    let default_order = file.bit_order.unwrap_or_else(|| "Lsb0".into());
+    file.structs
+        .into_iter()
+        .map(|st| {
+            Ok(StructDef {
+                bit_order: st.bit_order.unwrap_or_else(|| default_order.clone()),
+                version: st.version,
+                derives: st.derive.unwrap_or_else(|| file.derive.clone()),
+                fields: st
+                    .fields
+                    .into_iter()
+                    .map(|field| convert(field, &st.name))
+                    .collect::<Result<_, _>>()?,
+                name: st.name,
+                doc: st.doc,
+            })
+        })
+        .collect()
+}
+
+fn convert(field: Field, struct_name: &str) -> Result<FieldDef, Error> {
+    let path = format!("{}.{}", struct_name, field.name);
+    let mut options = Vec::new();
+    let mut int = |key: &str, value: Option<usize>| {
+        if let Some(value) = value {
+            options.push(format!("{} = {}", key, value));
+        }
+    };
+    int("bits", field.bits);
+    int("align", field.align);
+    int("sub_align", field.sub_align);
+    int("pad_bits", field.pad_bits);
+    int("pad_bytes", field.pad_bytes);
+    int("pad_bits_after", field.pad_bits_after);
+    int("pad_bytes_after", field.pad_bytes_after);
+    for (key, list) in [("sizes", field.sizes), ("aligns", field.aligns)] {
+        if let Some(list) = list {
+            let items: Vec<String> = list.iter().map(usize::to_string).collect();
+            options.push(format!("{} = [{}]", key, items.join(", ")));
+        }
+    }
+    // Options naming a field or method are written bare; expressions as strings.
+    for (key, name) in [
+        ("size_field", field.size_field),
+        ("size_func", field.size_func),
+        ("offset_field", field.offset_field),
+    ] {
+        if let Some(name) = name {
+            if syn::parse_str::<syn::Ident>(&name).is_err() {
+                return Err(Error::at(
+                    path,
+                    format!("`{}` must name a field or method, not `{}`", key, name),
+                ));
+            }
+            options.push(format!("{} = {}", key, name));
+        }
+    }
+    for (key, version) in [("since", field.since), ("until", field.until)] {
+        if let Some(version) = version {
+            options.push(format!("{} = {}", key, version));
+        }
+    }
+    for (key, text) in [
+        ("cond", field.cond),
+        ("default", field.default),
+        ("seek", field.seek),
+        ("relative_to", field.relative_to),
+        ("assert", field.assert),
+        ("msg", field.msg),
+        ("calc", field.calc),
+        ("ctx", field.ctx),
+    ] {
+        if let Some(text) = text {
+            let valid = match key {
+                "msg" | "ctx" => true,
+                "relative_to" => matches!(text.as_str(), "start" | "current"),
+                _ => syn::parse_str::<syn::Expr>(&text).is_ok(),
+            };
+            if !valid {
+                return Err(Error::at(
+                    path,
+                    format!("invalid `{}` value `{}`", key, text),
+                ));
+            }
+            options.push(format!("{} = {:?}", key, text));
+        }
+    }
+    if field.skip {
+        options.push("skip".into());
+    }
+    if field.verify_calc {
+        options.push("verify_calc".into());
+    }
+    Ok(FieldDef {
+        name: field.name,
+        ty: field.ty,
+        doc: field.doc,
+        options,
+    })
+}
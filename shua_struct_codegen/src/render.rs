@@ -0,0 +1,102 @@
+//! Rust source for [`StructDef`]s.
+
+use crate::{Error, StructDef};
+use std::fmt::Write;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "yield", "abstract", "become", "do", "final", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual",
+];
+
+pub(crate) fn render(structs: &[StructDef]) -> Result<String, Error> {
+    let mut out = String::from("// Generated by shua_struct_codegen. Do not edit.\n");
+    let mut names: Vec<&str> = Vec::new();
+    for st in structs {
+        if names.contains(&st.name.as_str()) {
+            return Err(Error::at(&st.name, "struct defined twice"));
+        }
+        names.push(&st.name);
+        render_struct(&mut out, st)?;
+    }
+    Ok(out)
+}
+
+fn render_struct(out: &mut String, st: &StructDef) -> Result<(), Error> {
+    check_ident(&st.name, &st.name)?;
+    if !matches!(st.bit_order.as_str(), "Lsb0" | "Msb0") {
+        return Err(Error::at(
+            &st.name,
+            format!(
+                "unknown bit order `{}`; expected `Lsb0` or `Msb0`",
+                st.bit_order
+            ),
+        ));
+    }
+    out.push('\n');
+    push_doc(out, &st.doc, "");
+    let mut derives = vec![String::from("Default")];
+    derives.extend(st.derives.iter().cloned());
+    derives.push("shua_struct::BinaryStruct".into());
+    let _ = writeln!(out, "#[derive({})]", derives.join(", "));
+    let mut struct_options = vec![format!("bit_order = shua_struct::{}", st.bit_order)];
+    if let Some(version) = &st.version {
+        if syn::parse_str::<syn::Ident>(version).is_ok() {
+            struct_options.push(format!("version = {}", version));
+        } else {
+            struct_options.push(format!("version = {:?}", version));
+        }
+    }
+    let _ = writeln!(out, "#[binary_struct({})]", struct_options.join(", "));
+    let _ = writeln!(out, "pub struct {} {{", st.name);
+
+    let mut names: Vec<&str> = Vec::new();
+    for field in &st.fields {
+        let path = format!("{}.{}", st.name, field.name);
+        check_ident(&field.name, &path)?;
+        if names.contains(&field.name.as_str()) {
+            return Err(Error::at(path, "field defined twice"));
+        }
+        names.push(&field.name);
+        syn::parse_str::<syn::Type>(&field.ty)
+            .map_err(|err| Error::at(&path, format!("invalid type `{}`: {}", field.ty, err)))?;
+        push_doc(out, &field.doc, "    ");
+        if !field.options.is_empty() {
+            let _ = writeln!(out, "    #[binary_field({})]", field.options.join(", "));
+        }
+        let name = if KEYWORDS.contains(&field.name.as_str()) {
+            format!("r#{}", field.name)
+        } else {
+            field.name.clone()
+        };
+        let _ = writeln!(out, "    pub {}: {},", name, field.ty);
+    }
+    out.push_str("}\n");
+    Ok(())
+}
+
+fn check_ident(name: &str, path: &str) -> Result<(), Error> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_";
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::at(
+            path,
+            format!("`{}` is not a valid Rust name", name),
+        ))
+    }
+}
+
+fn push_doc(out: &mut String, doc: &str, indent: &str) {
+    for line in doc.trim_end().lines() {
+        if line.is_empty() {
+            let _ = writeln!(out, "{}///", indent);
+        } else {
+            let _ = writeln!(out, "{}/// {}", indent, line);
+        }
+    }
+}
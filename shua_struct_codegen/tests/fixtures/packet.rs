@@ -0,0 +1,43 @@
+// Generated by shua_struct_codegen. Do not edit.
+
+/// A framed packet.
+#[derive(Default, shua_struct::BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Packet {
+    #[binary_field(assert = "self.magic == [202, 254]")]
+    pub magic: [u8; 2],
+    #[binary_field(bits = 3)]
+    pub version: u8,
+    pub urgent: bool,
+    /// Payload length in bytes.
+    #[binary_field(pad_bits = 4)]
+    pub len: u8,
+    #[binary_field(assert = "self.kind == 7")]
+    pub kind: u8,
+    pub header: Header,
+    #[binary_field(sizes = [2])]
+    pub points: Vec<Point>,
+    pub tags: [u8; 3],
+    #[binary_field(size_field = len)]
+    pub payload: Vec<u8>,
+    #[binary_field(cond = "self.len > 0 && !self.urgent")]
+    pub checksum: u16,
+    #[binary_field(seek = "self.len + 20")]
+    pub trailer: u8,
+}
+
+#[derive(Default, shua_struct::BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Header {
+    #[binary_field(bits = 12)]
+    pub flags: u16,
+    #[binary_field(bits = 4)]
+    pub mode: u8,
+}
+
+#[derive(Default, shua_struct::BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0)]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+}
@@ -0,0 +1,28 @@
+// Generated by shua_struct_codegen. Do not edit.
+
+/// One sensor reading.
+#[derive(Default, Debug, PartialEq, shua_struct::BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Msb0, version = version)]
+pub struct Reading {
+    #[binary_field(bits = 4)]
+    pub version: u8,
+    #[binary_field(bits = 3, pad_bits_after = 1)]
+    pub channel: u8,
+    /// Number of samples.
+    pub count: u8,
+    #[binary_field(size_field = count)]
+    pub samples: Vec<i16>,
+    #[binary_field(since = 2)]
+    pub scale: f32,
+    #[binary_field(cond = "self.count > 0", assert = "self.checksum != 0", msg = "zero checksum")]
+    pub checksum: u16,
+}
+
+#[derive(Default, Debug, PartialEq, shua_struct::BinaryStruct)]
+#[binary_struct(bit_order = shua_struct::Lsb0)]
+pub struct Batch {
+    #[binary_field(calc = "self.flags.len() as u8")]
+    pub len: u8,
+    #[binary_field(align = 8, size_field = len)]
+    pub flags: Vec<bool>,
+}
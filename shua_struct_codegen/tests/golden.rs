@@ -0,0 +1,100 @@
+//! Generated code for the schemas in `tests/fixtures`, compared with the checked-in
+//! `.rs` files next to them, which are also compiled here and round-tripped.
+
+use shua_struct::{BinaryField, BitSlice, Lsb0, Msb0};
+use std::path::Path;
+
+mod sensor {
+    include!("fixtures/sensor.rs");
+}
+
+mod packet {
+    include!("fixtures/packet.rs");
+}
+
+/// Generates code for `tests/fixtures/{name}` and compares it with the `.rs` file
+/// of the same stem; with `SHUA_BLESS=1` set, rewrites that file instead.
+fn golden(name: &str) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let source = std::fs::read_to_string(fixtures.join(name)).unwrap();
+    let code = match name.rsplit_once('.') {
+        Some((_, "ksy")) => shua_struct_codegen::from_ksy(&source),
+        _ => shua_struct_codegen::from_toml(&source),
+    }
+    .unwrap();
+    let path = fixtures.join(name).with_extension("rs");
+    if std::env::var_os("SHUA_BLESS").is_some() {
+        std::fs::write(&path, code).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(code, expected, "{} is out of date", path.display());
+}
+
+#[test]
+fn toml_matches_golden() {
+    golden("sensor.toml");
+}
+
+#[test]
+fn ksy_matches_golden() {
+    golden("packet.ksy");
+}
+
+#[test]
+fn toml_code_round_trips() {
+    let bytes = [
+        0x2A, 0x02, 0xFF, 0xFE, 0x01, 0x2C, 0x3F, 0xC0, 0x00, 0x00, 0xBE, 0xEF,
+    ];
+    let (reading, len) =
+        sensor::Reading::parse(BitSlice::<u8, Msb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(
+        reading,
+        sensor::Reading {
+            version: 2,
+            channel: 5,
+            count: 2,
+            samples: vec![-2, 300],
+            scale: 1.5,
+            checksum: 0xBEEF,
+        }
+    );
+    assert_eq!(len, bytes.len() * 8);
+    assert_eq!(reading.build(&None).unwrap().into_vec(), bytes);
+
+    let batch = sensor::Batch {
+        len: 0,
+        flags: vec![true, false, true],
+    };
+    let bytes = batch.build(&None).unwrap().into_vec();
+    assert_eq!(bytes, [0x03, 0x05]);
+    let (parsed, _) =
+        sensor::Batch::parse(BitSlice::<u8, Lsb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(parsed.len, 3);
+    assert_eq!(parsed.flags, batch.flags);
+}
+
+#[test]
+fn ksy_code_round_trips() {
+    let bytes = [
+        0xCA, 0xFE, 0xA0, 0x02, 0x07, 0xAB, 0xCD, 0x00, 0x01, 0xFF, 0xFF, 0x00, 0x02, 0xFF, 0xFE,
+        0x01, 0x02, 0x03, 0x10, 0x20, 0x12, 0x34, 0x99,
+    ];
+    let (packet, len) =
+        packet::Packet::parse(BitSlice::<u8, Msb0>::from_slice(&bytes), &None).unwrap();
+    assert_eq!(len, bytes.len() * 8);
+    assert_eq!(packet.version, 5);
+    assert!(!packet.urgent);
+    assert_eq!(packet.len, 2);
+    assert_eq!((packet.header.flags, packet.header.mode), (0xABC, 0xD));
+    assert_eq!(packet.points[1].y, -2);
+    assert_eq!(packet.tags, [1, 2, 3]);
+    assert_eq!(packet.payload, [0x10, 0x20]);
+    assert_eq!(packet.checksum, 0x1234);
+    assert_eq!(packet.trailer, 0x99);
+    assert_eq!(packet.build(&None).unwrap().into_vec(), bytes);
+
+    let mut bad = bytes;
+    bad[1] = 0;
+    assert!(packet::Packet::parse(BitSlice::<u8, Msb0>::from_slice(&bad), &None).is_err());
+}
@@ -1,183 +1,1439 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{Data, DeriveInput, Fields, Ident, LitInt, Path, Token, parse_macro_input};
+use proc_macro2::{Delimiter, TokenStream as TokenStream2, TokenTree};
+use quote::{ToTokens, format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, Field, Fields, Ident, LitInt, LitStr, Path, Token, Type,
+    parse_macro_input,
+};
+
+struct StructAttrs {
+    bit_order: Path,
+    ctx: Option<Type>,
+    version: Option<Expr>,
+    validate: Option<Ident>,
+    validate_on_build: bool,
+    flags: bool,
+    unknown: Option<Ident>,
+    bits: Option<usize>,
+    repr_c: bool,
+}
+
+#[derive(Default, Clone)]
+struct FieldAttrs {
+    size_field: Option<Ident>,
+    size_func: Option<Ident>,
+    align: Option<usize>,
+    sub_align: Option<u8>,
+    sizes: Vec<usize>,
+    aligns: Vec<usize>,
+    ctx: Option<Expr>,
+    cond: Option<Expr>,
+    since: Option<LitInt>,
+    until: Option<LitInt>,
+    skip: bool,
+    default: Option<Expr>,
+    pad_before: usize,
+    pad_after: usize,
+    offset_field: Option<Ident>,
+    seek: Option<Expr>,
+    relative_to: RelativeTo,
+    assert: Option<Expr>,
+    msg: Option<LitStr>,
+    parse_with: Option<Path>,
+    build_with: Option<Path>,
+    wire: Option<Type>,
+    map: Option<(Expr, bool)>,
+    unmap: Option<(Expr, bool)>,
+    calc: Option<Expr>,
+    verify_calc: bool,
+    bits: Option<usize>,
+    /// The option names as written, to point errors at.
+    keys: Vec<Ident>,
+}
+
+/// Where the byte offset of an `offset_field`/`seek` field is counted from.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum RelativeTo {
+    /// The start of the struct.
+    #[default]
+    Start,
+    /// The position the field would have in the sequential layout.
+    Current,
+}
 
 #[proc_macro_derive(BinaryStruct, attributes(binary_struct, binary_field))]
 pub fn binary_struct_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let mut bit_order: Path = syn::parse_str("shua_struct::Lsb0").unwrap();
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    for attr in input.attrs.iter() {
-        if attr.path().is_ident("binary_struct") {
-            attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("bit_order") {
-                    meta.input.parse::<Token![=]>()?;
-                    bit_order = meta.input.parse()?;
-                } else {
-                    return Err(meta.error("expected `bit_order`"));
-                }
-                Ok(())
-            })
-            .unwrap();
-        }
-    }
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let attrs = parse_struct_attrs(input)?;
+    let bit_order = &attrs.bit_order;
 
     let struct_name = &input.ident;
+    if attrs.flags {
+        return Ok(flags_impl(struct_name, &attrs));
+    }
     let fields_named = match &input.data {
         Data::Struct(data) => {
             if let Fields::Named(fields) = &data.fields {
                 fields.named.clone()
             } else {
-                panic!("BinaryStruct only supports structs with named fields");
+                return Err(syn::Error::new_spanned(
+                    &data.fields,
+                    "BinaryStruct only supports structs with named fields",
+                ));
             }
         }
-        _ => panic!("BinaryStruct only works on structs"),
+        Data::Enum(data) => {
+            return Err(syn::Error::new_spanned(
+                data.enum_token,
+                "BinaryStruct only works on structs",
+            ));
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "BinaryStruct only works on structs",
+            ));
+        }
     };
+    let fields: Vec<&Field> = fields_named.iter().collect();
+    let parsed_attrs = parse_all_field_attrs(&fields, &attrs)?;
+    let offset_targets: Vec<&Ident> = parsed_attrs
+        .iter()
+        .filter_map(|attrs| attrs.offset_field.as_ref())
+        .collect();
     let mut parse_stmts = Vec::new();
     let mut build_stmts = Vec::new();
-    let mut field_names = Vec::new();
-    for field in fields_named.iter() {
+    let mut deferred_build_stmts = Vec::new();
+    let mut build_prelude = Vec::new();
+    let mut parse_prelude = Vec::new();
+    let mut verify_stmts = Vec::new();
+    let mut schema_fields = Vec::new();
+    let mut c_aligns = Vec::new();
+    // Options of the fields seen so far; offset back-fills build with their field's options.
+    let mut opts_by_field: Vec<(&Ident, TokenStream2)> = Vec::new();
+    for (field, mut field_attrs) in fields_named.iter().zip(parsed_attrs.iter().cloned()) {
         let field_name = field.ident.as_ref().unwrap();
         let field_type = &field.ty;
-        let mut opt_size_field: Option<Ident> = None;
-        let mut opt_size_func: Option<Ident> = None;
-        let mut opt_align: Option<usize> = None;
-        let mut opt_sub_align: Option<u8> = None;
-        let has_binary_field_attr = field
-            .attrs
-            .iter()
-            .any(|attr| attr.path().is_ident("binary_field"));
-        for attr in &field.attrs {
-            if attr.path().is_ident("binary_field") {
-                let _ = attr.parse_nested_meta(|meta| {
-                    if meta.path.is_ident("size_field") {
-                        meta.input.parse::<Token![=]>()?;
-                        opt_size_field = Some(meta.input.parse()?);
-                        return Ok(());
-                    }
-                    if meta.path.is_ident("size_func") {
-                        meta.input.parse::<Token![=]>()?;
-                        opt_size_func = Some(meta.input.parse()?);
-                        return Ok(());
-                    }
-                    if meta.path.is_ident("align") {
-                        meta.input.parse::<Token![=]>()?;
-                        let align_lit: LitInt = meta.input.parse()?;
-                        let align_val: usize = align_lit.base10_parse()?;
-                        opt_align = Some(align_val);
-                        return Ok(());
-                    }
-                    if meta.path.is_ident("sub_align") {
-                        meta.input.parse::<Token![=]>()?;
-                        let align_lit: LitInt = meta.input.parse()?;
-                        let align_val: u8 = align_lit.base10_parse()?;
-                        opt_sub_align = Some(align_val);
-                        return Ok(());
+
+        // `sub_align = N` moves `align` from the field itself to nesting level N - 1.
+        let field_align = match (field_attrs.align, field_attrs.sub_align) {
+            (Some(align), None) => Some(align),
+            (Some(align), Some(level)) => {
+                let level = level as usize - 1;
+                if field_attrs.aligns.len() <= level {
+                    field_attrs.aligns.resize(level + 1, 0);
+                }
+                field_attrs.aligns[level] = align;
+                None
+            }
+            (None, _) => None,
+        };
+        let field_opts = field_opts(&field_attrs);
+        opts_by_field.push((field_name, field_opts.clone()));
+        schema_fields.push(field_schema(field, &field_attrs, field_align, bit_order));
+
+        let align_parse_logic = if let Some(align_val) = field_align {
+            quote! {
+                let remainder = l % #align_val;
+                if remainder != 0 {
+                    l += #align_val - remainder;
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let cond = field_cond(&field_attrs, attrs.version.as_ref());
+        let name_str = field_name.to_string();
+        let wire_type = field_attrs.wire.as_ref().unwrap_or(field_type);
+        let c_align = if field_attrs.skip {
+            quote! { 0 }
+        } else if field_attrs.parse_with.is_some() {
+            quote! { 8 }
+        } else {
+            quote! { <#wire_type as shua_struct::BinaryField<#bit_order>>::C_ALIGN }
+        };
+        // `repr_c` moves each field to where a C compiler would place it.
+        let (c_place_parse, c_place_build) = if attrs.repr_c && !field_attrs.skip {
+            let bits = match field_attrs.bits {
+                Some(bits) => quote! { Some(#bits) },
+                None => quote! { None },
+            };
+            (
+                quote! { offset = shua_struct::c::place(offset, #c_align, #bits); },
+                quote! { bv.resize(shua_struct::c::place(bv.len(), #c_align, #bits), false); },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
+        c_aligns.push(c_align);
+        let (parse_call, traced_call) = match &field_attrs.parse_with {
+            Some(parse_with) => (
+                quote! { #parse_with(rest) },
+                quote! { |_| #parse_with(rest) },
+            ),
+            None => (
+                quote! {
+                    <#wire_type as shua_struct::BinaryField<#bit_order>>::parse(rest, &#field_opts)
+                },
+                quote! {
+                    |span| <#wire_type as shua_struct::BinaryField<#bit_order>>::parse_traced(rest, &#field_opts, span)
+                },
+            ),
+        };
+        // `calc` fields are built from their computed value instead of the stored one.
+        let (calc_build, field_value) = match &field_attrs.calc {
+            Some(calc) => (
+                quote! { let calc_val: #field_type = #calc; },
+                quote! { &calc_val },
+            ),
+            None => (quote! {}, quote! { &self.#field_name }),
+        };
+        let wire_value = if field_attrs.wire.is_some() {
+            quote! { &wire_val }
+        } else {
+            field_value.clone()
+        };
+        let build_call = match &field_attrs.build_with {
+            Some(build_with) => quote! { #build_with(#wire_value) },
+            None => quote! {
+                <#wire_type as shua_struct::BinaryField<#bit_order>>::build(#wire_value, &#field_opts)
+            },
+        };
+        let (map_parse, map_build) = map_logic(&field_attrs, &field_value, &name_str);
+        let record_calc = match &field_attrs.calc {
+            Some(calc) if field_attrs.verify_calc => {
+                let calc_var = format_ident!("__shua_calc_{}", field_name);
+                let msg = format!("`{}` does not match its computed value", field_name);
+                parse_prelude.push(quote! { let mut #calc_var: Option<usize> = None; });
+                verify_stmts.push(quote! {
+                    if let Some(bit) = #calc_var {
+                        if self.#field_name != (#calc) {
+                            return Err(shua_struct::Error::new(#msg).in_field(#name_str, bit));
+                        }
                     }
-                    Err(meta.error(
-                        "expected `size_field = ...`, `size_func = ...`, `align = ...`, or `sub_align = ...`",
-                    ))
                 });
+                quote! { #calc_var = Some(offset); }
             }
-        }
-        field_names.push(field_name);
-        let has_opts = opt_size_field.is_some()
-            || opt_size_func.is_some()
-            || opt_align.is_some()
-            || opt_sub_align.is_some()
-            || has_binary_field_attr;
-        let align_val = opt_align.unwrap_or(0);
-        let sub_align_val = opt_sub_align.unwrap_or(0);
-        let size_calc = if let Some(size_field) = opt_size_field.clone() {
-            quote! { s.#size_field.into() }
-        } else if let Some(size_func) = opt_size_func.clone() {
-            quote! { s.#size_func() }
+            _ => quote! {},
+        };
+        let assert_parse = assert_logic(&field_attrs, &name_str, quote! { offset });
+        let assert_build = if attrs.validate_on_build {
+            assert_logic(&field_attrs, &name_str, quote! { bv.len() })
         } else {
-            quote! { 0 }
+            quote! {}
+        };
+        let (pad_before_parse, pad_before_build) = pad_logic(field_attrs.pad_before);
+        let (pad_after_parse, pad_after_build) = pad_logic(field_attrs.pad_after);
+        let default_parse = match &field_attrs.default {
+            Some(default) => quote! { self.#field_name = #default; },
+            None => quote! {},
         };
-        let field_opts_parse = if has_opts {
+        let field_parse = if field_attrs.skip {
+            default_parse.clone()
+        } else {
             quote! {
-                Some(shua_struct::Options {
-                    size: #size_calc,
-                    align: #align_val,
-                    sub_align: std::cell::Cell::new(#sub_align_val),
-                })
+                let rest = bits.get(offset..).ok_or_else(|| {
+                    shua_struct::Error::new("not enough bits").in_field(#name_str, offset)
+                })?;
+                let (val, mut l) = match trace.as_deref_mut() {
+                    Some(span) => span.trace_field(#name_str, offset, rest, #traced_call),
+                    None => #parse_call,
+                }
+                .map_err(|e| Into::<shua_struct::Error>::into(e).in_field(#name_str, offset))?;
+                #map_parse
+                #align_parse_logic
+                self.#field_name = val;
+                if let Some(span) = trace.as_deref_mut() {
+                    shua_struct::__private::show_last(
+                        span,
+                        (&shua_struct::__private::Show(&self.#field_name)).show(),
+                    );
+                }
+                #assert_parse
+                #record_calc
+                offset += l;
             }
-        } else {
-            quote! { None }
         };
-        let align_parse_logic = if opt_align.is_some() && opt_sub_align.is_none() {
+        let is_pointer = field_attrs.offset_field.is_some() || field_attrs.seek.is_some();
+        let target = match (&field_attrs.offset_field, &field_attrs.seek) {
+            (Some(offset_field), _) => quote! { self.#offset_field },
+            (None, Some(seek)) => quote! { (#seek) },
+            (None, None) => quote! {},
+        };
+        if is_pointer {
+            let base = match field_attrs.relative_to {
+                RelativeTo::Start => quote! { 0 },
+                RelativeTo::Current => quote! { offset },
+            };
+            let msg = format!(
+                "{} parse error: offset of `{}` out of range",
+                struct_name, field_name
+            );
+            parse_stmts.push(quote! {
+                if #cond {
+                    let mut offset = usize::try_from(#target)
+                        .ok()
+                        .and_then(|target| target.checked_mul(8))
+                        .and_then(|target| target.checked_add(#base))
+                        .filter(|&target| target <= bits.len())
+                        .ok_or(#msg)?;
+                    #pad_before_parse
+                    #field_parse
+                    #pad_after_parse
+                    end = end.max(offset);
+                } else {
+                    #default_parse
+                }
+            });
+        } else {
+            parse_stmts.push(quote! {
+                if #cond {
+                    #pad_before_parse
+                    #c_place_parse
+                    #field_parse
+                    #pad_after_parse
+                } else {
+                    #default_parse
+                }
+            });
+        }
+        let align_build_logic = if let Some(align_val) = field_align {
             quote! {
-                let remainder = l % #align_val;
+                let remainder = field_bv.len() % #align_val;
                 if remainder != 0 {
-                    l += #align_val - remainder;
+                    field_bv.resize(field_bv.len() + (#align_val - remainder), false);
                 }
             }
         } else {
             quote! {}
         };
-        parse_stmts.push(quote! {
-            let field_opts = #field_opts_parse;
-            let (val, mut l) = <#field_type as shua_struct::BinaryField<#bit_order>>::parse(
-                &bits[offset..],
-                &field_opts
-            )?;
-            #align_parse_logic
-            s.#field_name = val;
-            offset += l;
-        });
-        let size_calc_build = if let Some(size_field) = opt_size_field {
-            quote! { self.#size_field.into() }
-        } else if let Some(size_func) = opt_size_func {
-            quote! { self.#size_func() }
+        let record_start = if offset_targets.contains(&field_name) {
+            let start_var = start_var(field_name);
+            build_prelude.push(quote! { let mut #start_var: Option<usize> = None; });
+            quote! { #start_var = Some(bv.len()); }
         } else {
-            quote! { 0 }
+            quote! {}
         };
-        let field_opts_build = if has_opts {
+        let field_build = if field_attrs.skip {
+            quote! {}
+        } else {
             quote! {
-                Some(shua_struct::Options {
-                    size: #size_calc_build,
-                    align: #align_val,
-                    sub_align: std::cell::Cell::new(#sub_align_val),
-                })
+                #assert_build
+                #calc_build
+                #map_build
+                let mut field_bv = #build_call
+                    .map_err(|e| Into::<shua_struct::Error>::into(e).in_field(#name_str, bv.len()))?;
+                #align_build_logic
+                #record_start
+                bv.extend(field_bv);
             }
+        };
+        if !is_pointer {
+            build_stmts.push(quote! {
+                if #cond {
+                    #pad_before_build
+                    #c_place_build
+                    #field_build
+                    #pad_after_build
+                }
+            });
+            continue;
+        }
+
+        // Pointed-to data is laid out after the sequential fields, starting on a byte boundary.
+        let base = match field_attrs.relative_to {
+            RelativeTo::Start => quote! { 0 },
+            RelativeTo::Current => {
+                let base_var = format_ident!("__shua_base_{}", field_name);
+                build_stmts.push(quote! { let #base_var = bv.len(); });
+                quote! { #base_var }
+            }
+        };
+        let place = if field_attrs.offset_field.is_some() {
+            quote! { let pos = bv.len(); }
         } else {
-            quote! { None }
+            let msg = format!(
+                "{} build error: `{}` overlaps preceding data",
+                struct_name, field_name
+            );
+            quote! {
+                let pos = usize::try_from(#target)
+                    .ok()
+                    .and_then(|target| target.checked_mul(8))
+                    .and_then(|target| target.checked_add(base))
+                    .filter(|&pos| pos >= bv.len())
+                    .ok_or(#msg)?;
+                bv.resize(pos, false);
+            }
         };
-        let align_build_logic = if opt_align.is_some() && opt_sub_align.is_none() {
+        let backfill = if let Some(offset_field) = &field_attrs.offset_field {
+            let offset_type = &fields
+                .iter()
+                .find(|field| field.ident.as_ref() == Some(offset_field))
+                .ok_or_else(|| syn::Error::new(offset_field.span(), "names no field"))?
+                .ty;
+            let offset_opts = opts_by_field
+                .iter()
+                .find(|(name, _)| *name == offset_field)
+                .map(|(_, opts)| opts.clone())
+                .unwrap_or_else(|| quote! { None });
+            let start_var = start_var(offset_field);
+            let range_msg = format!(
+                "{} build error: offset of `{}` does not fit `{}`",
+                struct_name, field_name, offset_field
+            );
+            let missing_msg = format!(
+                "{} build error: offset field `{}` is not on the wire",
+                struct_name, offset_field
+            );
             quote! {
-                let remainder = field_bv.len() % #align_val;
-                if remainder != 0 {
-                    field_bv.resize(field_bv.len() + (#align_val - remainder), false);
-                }
+                let value = <#offset_type as TryFrom<usize>>::try_from((pos - base) / 8)
+                    .map_err(|_| #range_msg)?;
+                let value_bv = <#offset_type as shua_struct::BinaryField<#bit_order>>::build(&value, &#offset_opts)?;
+                let start = #start_var.ok_or(#missing_msg)?;
+                bv[start..start + value_bv.len()].copy_from_bitslice(&value_bv);
             }
         } else {
             quote! {}
         };
-        build_stmts.push(quote! {
-            let field_opts = #field_opts_build;
-            let mut field_bv = <#field_type as shua_struct::BinaryField<#bit_order>>::build(&self.#field_name, &field_opts)?;
-            #align_build_logic
-            bv.extend(field_bv);
+        deferred_build_stmts.push(quote! {
+            if #cond {
+                let base = #base;
+                let remainder = (bv.len() - base) % 8;
+                if remainder != 0 {
+                    bv.resize(bv.len() + (8 - remainder), false);
+                }
+                #place
+                #pad_before_build
+                #field_build
+                #pad_after_build
+                #backfill
+            }
         });
     }
+    let has_pointers = parsed_attrs
+        .iter()
+        .any(|attrs| attrs.offset_field.is_some() || attrs.seek.is_some());
+    let (end_decl, parse_result) = if has_pointers {
+        (quote! { let mut end = 0; }, quote! { offset.max(end) })
+    } else {
+        (quote! {}, quote! { offset })
+    };
+    // A C struct is as large as a multiple of its widest member's alignment.
+    let (c_align_const, parse_result, c_tail_build, schema_fields) = if attrs.repr_c {
+        let c_align = quote! { <Self as shua_struct::BinaryField<#bit_order>>::C_ALIGN };
+        (
+            quote! {
+                const C_ALIGN: usize = {
+                    let mut align = 8;
+                    #(if #c_aligns > align {
+                        align = #c_aligns;
+                    })*
+                    align
+                };
+            },
+            quote! { (#parse_result).next_multiple_of(#c_align) },
+            quote! { bv.resize(bv.len().next_multiple_of(#c_align), false); },
+            quote! {{
+                let mut fields = shua_struct::__private::Vec::from([#(#schema_fields),*]);
+                shua_struct::c::pad_fields(&mut fields, &[#(#c_aligns),*], #c_align);
+                fields
+            }},
+        )
+    } else {
+        (
+            quote! {},
+            parse_result,
+            quote! {},
+            quote! { shua_struct::__private::Vec::from([#(#schema_fields),*]) },
+        )
+    };
+    let validate_parse = match &attrs.validate {
+        Some(validate) => quote! {
+            self.#validate().map_err(Into::<shua_struct::Error>::into)?;
+        },
+        None => quote! {},
+    };
+    let validate_build = if attrs.validate_on_build {
+        validate_parse.clone()
+    } else {
+        quote! {}
+    };
+    let struct_doc = doc_string(&input.attrs);
+    let order_name = bit_order
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    let version = match &attrs.version {
+        Some(version) => {
+            let version = source_string(version);
+            quote! { Some(#version.into()) }
+        }
+        None => quote! { None },
+    };
+    let name_str = struct_name.to_string();
+    let parse_ctx = ctx_binding(struct_name, attrs.ctx.as_ref(), "parse");
+    let build_ctx = ctx_binding(struct_name, attrs.ctx.as_ref(), "build");
     let expanded = quote! {
+        impl #struct_name {
+            /// Describes the wire layout of this type.
+            pub fn schema() -> shua_struct::Schema {
+                shua_struct::Schema {
+                    name: #name_str.into(),
+                    doc: #struct_doc.into(),
+                    bit_order: #order_name.into(),
+                    version: #version,
+                    fields: #schema_fields,
+                }
+            }
+
+            #[doc(hidden)]
+            fn __shua_parse(
+                &mut self,
+                bits: &shua_struct::BitSlice<u8, #bit_order>,
+                outer_opts: &Option<shua_struct::Options>,
+                mut trace: Option<&mut shua_struct::Span<#bit_order>>,
+            ) -> Result<usize, shua_struct::Error> {
+                #[allow(unused_imports)]
+                use shua_struct::__private::{ShowDebug as _, ShowNone as _};
+                #parse_ctx
+                let mut offset = 0;
+                #end_decl
+                #(#parse_prelude)*
+                #(#parse_stmts)*
+                #(#verify_stmts)*
+                #validate_parse
+                Ok(#parse_result)
+            }
+        }
         impl shua_struct::BinaryField<#bit_order> for #struct_name {
+            #c_align_const
+
             fn parse(
                 bits: &shua_struct::BitSlice<u8, #bit_order>,
                 outer_opts: &Option<shua_struct::Options>,
-            ) -> Result<(Self, usize), String> {
+            ) -> Result<(Self, usize), shua_struct::Error> {
                 let mut s = Self::default();
-                let mut offset = 0;
-                #(#parse_stmts)*
+                let offset = s.__shua_parse(bits, outer_opts, None)?;
                 Ok((s, offset))
             }
-            fn build(&self, outer_opts: &Option<shua_struct::Options>) -> Result<shua_struct::BitVec<u8, #bit_order>, String> {
+            fn build(&self, outer_opts: &Option<shua_struct::Options>) -> Result<shua_struct::BitVec<u8, #bit_order>, shua_struct::Error> {
+                #build_ctx
+                #validate_build
                 let mut bv = shua_struct::BitVec::new();
+                #(#build_prelude)*
                 #(#build_stmts)*
+                #(#deferred_build_stmts)*
+                #c_tail_build
                 Ok(bv)
             }
+            fn parse_traced(
+                bits: &shua_struct::BitSlice<u8, #bit_order>,
+                outer_opts: &Option<shua_struct::Options>,
+                span: &mut shua_struct::Span<#bit_order>,
+            ) -> Result<(Self, usize), shua_struct::Error> {
+                let mut s = Self::default();
+                let offset = s.__shua_parse(bits, outer_opts, Some(span))?;
+                Ok((s, offset))
+            }
+            fn layout() -> shua_struct::Layout {
+                shua_struct::Layout::Struct(Self::schema())
+            }
         }
     };
-    TokenStream::from(expanded)
+    Ok(expanded)
+}
+
+/// `#[binary_struct(flags)]`: the type implements `bitflags::Flags` and is stored as its bits.
+fn flags_impl(name: &Ident, attrs: &StructAttrs) -> TokenStream2 {
+    let bit_order = &attrs.bit_order;
+    let unknown = attrs
+        .unknown
+        .clone()
+        .unwrap_or_else(|| format_ident!("Retain"));
+    // A struct-level `bits` is the default width; a field's `bits` option still wins.
+    let opts = match attrs.bits {
+        Some(bits) => quote! {
+            &Some(match outer_opts {
+                Some(opts) if opts.bits.is_some() => opts.clone(),
+                Some(opts) => shua_struct::Options { bits: Some(#bits), ..opts.clone() },
+                None => shua_struct::Options { bits: Some(#bits), ..Default::default() },
+            })
+        },
+        None => quote! { outer_opts },
+    };
+    let default_bits = match attrs.bits {
+        Some(bits) => quote! { Some(#bits) },
+        None => quote! { None },
+    };
+    let expanded = quote! {
+        impl shua_struct::BinaryField<#bit_order> for #name {
+            const C_ALIGN: usize = shua_struct::field::flags::c_align::<Self, #bit_order>();
+
+            fn parse(
+                bits: &shua_struct::BitSlice<u8, #bit_order>,
+                outer_opts: &Option<shua_struct::Options>,
+            ) -> Result<(Self, usize), shua_struct::Error> {
+                shua_struct::field::flags::parse(
+                    bits,
+                    #opts,
+                    shua_struct::field::flags::UnknownBits::#unknown,
+                )
+            }
+            fn build(&self, outer_opts: &Option<shua_struct::Options>) -> Result<shua_struct::BitVec<u8, #bit_order>, shua_struct::Error> {
+                shua_struct::field::flags::build(
+                    self,
+                    #opts,
+                    shua_struct::field::flags::UnknownBits::#unknown,
+                )
+            }
+            fn layout() -> shua_struct::Layout {
+                shua_struct::field::flags::layout::<Self, #bit_order>(#default_bits)
+            }
+        }
+    };
+    expanded
+}
+
+fn parse_struct_attrs(input: &DeriveInput) -> syn::Result<StructAttrs> {
+    let mut attrs = StructAttrs {
+        bit_order: syn::parse_quote!(shua_struct::Lsb0),
+        ctx: None,
+        version: None,
+        validate: None,
+        validate_on_build: false,
+        flags: false,
+        unknown: None,
+        bits: None,
+        repr_c: false,
+    };
+    let mut flags_only = None;
+    for attr in input.attrs.iter() {
+        if attr.path().is_ident("binary_struct") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bit_order") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.bit_order = meta.input.parse()?;
+                } else if meta.path.is_ident("ctx") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.ctx = Some(meta.input.parse()?);
+                } else if meta.path.is_ident("version") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.version = Some(if meta.input.peek(LitStr) {
+                        meta.input.parse::<LitStr>()?.parse()?
+                    } else {
+                        let field: Ident = meta.input.parse()?;
+                        syn::parse_quote!(self.#field)
+                    });
+                } else if meta.path.is_ident("validate") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.validate = Some(meta.input.parse()?);
+                } else if meta.path.is_ident("validate_on_build") {
+                    attrs.validate_on_build = true;
+                } else if meta.path.is_ident("repr_c") {
+                    attrs.repr_c = true;
+                } else if meta.path.is_ident("flags") {
+                    attrs.flags = true;
+                } else if meta.path.is_ident("unknown") {
+                    flags_only.get_or_insert(meta.path.span());
+                    meta.input.parse::<Token![=]>()?;
+                    let unknown_lit: LitStr = meta.input.parse()?;
+                    let variant = match unknown_lit.value().as_str() {
+                        "retain" => "Retain",
+                        "reject" => "Reject",
+                        "truncate" => "Truncate",
+                        _ => {
+                            return Err(
+                                meta.error("expected `\"retain\"`, `\"reject\"`, or `\"truncate\"`")
+                            );
+                        }
+                    };
+                    attrs.unknown = Some(format_ident!("{}", variant));
+                } else if meta.path.is_ident("bits") {
+                    flags_only.get_or_insert(meta.path.span());
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.bits = Some(parse_nonzero(meta.input)?);
+                } else {
+                    let key = meta.path.clone();
+                    return Err(meta.error(format!(
+                        "unknown option `{}`; expected `bit_order`, `ctx`, `version`, `validate`, `validate_on_build`, `repr_c`, `flags`, `unknown`, or `bits`",
+                        quote!(#key)
+                    )));
+                }
+                Ok(())
+            })?;
+        }
+    }
+    if let Some(span) = flags_only
+        && !attrs.flags
+    {
+        return Err(syn::Error::new(span, "only valid together with `flags`"));
+    }
+    Ok(attrs)
+}
+
+/// Parses every field's attributes and checks them against each other, reporting all errors at once.
+fn parse_all_field_attrs(
+    fields: &[&Field],
+    struct_attrs: &StructAttrs,
+) -> syn::Result<Vec<FieldAttrs>> {
+    let mut parsed = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut push_error = |error: syn::Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+    for (index, field) in fields.iter().enumerate() {
+        match parse_field_attrs(field)
+            .and_then(|attrs| check_field_attrs(&attrs, index, fields, struct_attrs).map(|_| attrs))
+        {
+            Ok(attrs) => parsed.push(attrs),
+            Err(error) => push_error(error),
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+    for offset_field in parsed
+        .iter()
+        .filter_map(|attrs| attrs.offset_field.as_ref())
+    {
+        check_offset_target(offset_field, fields, &parsed)?;
+    }
+    Ok(parsed)
+}
+
+/// Rejects offset fields whose wire value is not the plain offset the build back-fills.
+fn check_offset_target(
+    offset_field: &Ident,
+    fields: &[&Field],
+    parsed: &[FieldAttrs],
+) -> syn::Result<()> {
+    let Some(target) = fields
+        .iter()
+        .position(|field| field.ident.as_ref() == Some(offset_field))
+        .map(|position| &parsed[position])
+    else {
+        return Ok(());
+    };
+    let conflict = [
+        ("skip", target.skip),
+        ("wire", target.wire.is_some()),
+        ("parse_with", target.parse_with.is_some()),
+        ("build_with", target.build_with.is_some()),
+        ("calc", target.calc.is_some()),
+    ]
+    .into_iter()
+    .find(|(_, set)| *set);
+    match conflict {
+        Some((key, _)) => Err(syn::Error::new(
+            offset_field.span(),
+            format!(
+                "`offset_field = {}` cannot back-fill a field with `{}`",
+                offset_field, key
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Checks options that depend on each other or on other fields.
+fn check_field_attrs(
+    attrs: &FieldAttrs,
+    index: usize,
+    fields: &[&Field],
+    struct_attrs: &StructAttrs,
+) -> syn::Result<()> {
+    // Size and offset fields are read from `self` while parsing, so they must come first.
+    let check_earlier = |name: &Ident, key: &str| match fields
+        .iter()
+        .position(|field| field.ident.as_ref() == Some(name))
+    {
+        None => Err(syn::Error::new(
+            name.span(),
+            format!("`{} = {}` names no field of this struct", key, name),
+        )),
+        Some(position) if position >= index => Err(syn::Error::new(
+            name.span(),
+            format!(
+                "`{} = {}` must name a field declared before this one",
+                key, name
+            ),
+        )),
+        Some(_) => Ok(()),
+    };
+    if let Some(size_field) = &attrs.size_field {
+        check_earlier(size_field, "size_field")?;
+        if let Some(size_func) = &attrs.size_func {
+            return Err(syn::Error::new(
+                size_func.span(),
+                "`size_field` and `size_func` cannot both be set",
+            ));
+        }
+    }
+    if let Some(offset_field) = &attrs.offset_field {
+        check_earlier(offset_field, "offset_field")?;
+        if let Some(seek) = &attrs.seek {
+            return Err(syn::Error::new_spanned(
+                seek,
+                "`offset_field` and `seek` cannot both be set",
+            ));
+        }
+    }
+    if let Some(wire) = &attrs.wire {
+        if attrs.map.is_none() {
+            return Err(syn::Error::new(
+                wire.span(),
+                "`wire` requires `map` or `try_map`",
+            ));
+        }
+        if attrs.unmap.is_none() {
+            return Err(syn::Error::new(
+                wire.span(),
+                "`wire` requires `unmap` or `try_unmap`",
+            ));
+        }
+    } else if let Some((map, _)) = attrs.map.as_ref().or(attrs.unmap.as_ref()) {
+        return Err(syn::Error::new_spanned(
+            map,
+            "`map`/`unmap` require `wire = ...`",
+        ));
+    }
+    if let Some(range) = attrs.since.as_ref().or(attrs.until.as_ref()) {
+        let Some(version) = &struct_attrs.version else {
+            return Err(syn::Error::new(
+                range.span(),
+                "`since`/`until` require `#[binary_struct(version = ...)]`",
+            ));
+        };
+        // The version is read from `self` while parsing, like size and offset fields.
+        for name in self_fields(version.to_token_stream()) {
+            match fields
+                .iter()
+                .position(|field| field.ident.as_ref() == Some(&name))
+            {
+                Some(position) if position < index => {}
+                _ => {
+                    return Err(syn::Error::new(
+                        range.span(),
+                        format!(
+                            "`since`/`until` need the version field `{}` declared before this one",
+                            name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    // Options that only adjust another option do nothing on their own.
+    let needs = [
+        (
+            "sub_align",
+            attrs.align.is_some(),
+            "`sub_align` requires `align`",
+        ),
+        ("msg", attrs.assert.is_some(), "`msg` requires `assert`"),
+        (
+            "verify_calc",
+            attrs.calc.is_some(),
+            "`verify_calc` requires `calc`",
+        ),
+        (
+            "relative_to",
+            attrs.offset_field.is_some() || attrs.seek.is_some(),
+            "`relative_to` requires `offset_field` or `seek`",
+        ),
+        (
+            "parse_with",
+            attrs.build_with.is_some(),
+            "`parse_with` requires `build_with`; use `with = ...` for a module with both",
+        ),
+        (
+            "build_with",
+            attrs.parse_with.is_some(),
+            "`build_with` requires `parse_with`; use `with = ...` for a module with both",
+        ),
+    ];
+    for (key, met, msg) in needs {
+        if !met && let Some(key) = attrs.keys.iter().find(|written| *written == key) {
+            return Err(syn::Error::new(key.span(), msg));
+        }
+    }
+    // C structs have one fixed layout, which the schema records padding for.
+    if struct_attrs.repr_c {
+        let variable = [
+            ("cond", attrs.cond.as_ref().map(Spanned::span)),
+            ("since", attrs.since.as_ref().map(Spanned::span)),
+            ("until", attrs.until.as_ref().map(Spanned::span)),
+            ("size_field", attrs.size_field.as_ref().map(Spanned::span)),
+            ("size_func", attrs.size_func.as_ref().map(Spanned::span)),
+            (
+                "offset_field",
+                attrs.offset_field.as_ref().map(Spanned::span),
+            ),
+            ("seek", attrs.seek.as_ref().map(Spanned::span)),
+        ];
+        if let Some((key, Some(span))) = variable.into_iter().find(|(_, span)| span.is_some()) {
+            return Err(syn::Error::new(
+                span,
+                format!("`{}` is not supported with `repr_c`", key),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn parse_field_attrs(field: &Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in &field.attrs {
+        if attr.path().is_ident("binary_field") {
+            attr.parse_nested_meta(|meta| {
+                if let Some(key) = meta.path.get_ident() {
+                    attrs.keys.push(key.clone());
+                }
+                if meta.path.is_ident("size_field") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.size_field = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("size_func") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.size_func = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("align") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.align = Some(parse_nonzero(meta.input)?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("sub_align") {
+                    meta.input.parse::<Token![=]>()?;
+                    let align_lit: LitInt = meta.input.parse()?;
+                    attrs.sub_align = match align_lit.base10_parse()? {
+                        0 => {
+                            return Err(syn::Error::new(
+                                align_lit.span(),
+                                "`sub_align` levels start at 1; drop it to align the field itself",
+                            ));
+                        }
+                        level => Some(level),
+                    };
+                    return Ok(());
+                }
+                if meta.path.is_ident("sizes") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.sizes = parse_usize_list(meta.input)?;
+                    return Ok(());
+                }
+                if meta.path.is_ident("aligns") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.aligns = parse_usize_list(meta.input)?;
+                    return Ok(());
+                }
+                if meta.path.is_ident("ctx") {
+                    meta.input.parse::<Token![=]>()?;
+                    let expr_lit: LitStr = meta.input.parse()?;
+                    attrs.ctx = Some(expr_lit.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("cond") {
+                    meta.input.parse::<Token![=]>()?;
+                    let expr_lit: LitStr = meta.input.parse()?;
+                    attrs.cond = Some(expr_lit.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("since") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.since = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("until") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.until = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                    return Ok(());
+                }
+                if meta.path.is_ident("default") {
+                    meta.input.parse::<Token![=]>()?;
+                    let expr_lit: LitStr = meta.input.parse()?;
+                    attrs.default = Some(expr_lit.parse()?);
+                    return Ok(());
+                }
+                for (key, unit, after) in [
+                    ("pad_bits", 1, false),
+                    ("pad_bytes", 8, false),
+                    ("pad_bits_after", 1, true),
+                    ("pad_bytes_after", 8, true),
+                ] {
+                    if meta.path.is_ident(key) {
+                        meta.input.parse::<Token![=]>()?;
+                        let pad_lit: LitInt = meta.input.parse()?;
+                        let pad = pad_lit.base10_parse::<usize>()? * unit;
+                        if after {
+                            attrs.pad_after += pad;
+                        } else {
+                            attrs.pad_before += pad;
+                        }
+                        return Ok(());
+                    }
+                }
+                if meta.path.is_ident("offset_field") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.offset_field = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("seek") {
+                    meta.input.parse::<Token![=]>()?;
+                    let expr_lit: LitStr = meta.input.parse()?;
+                    attrs.seek = Some(expr_lit.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("relative_to") {
+                    meta.input.parse::<Token![=]>()?;
+                    let relative_lit: LitStr = meta.input.parse()?;
+                    attrs.relative_to = match relative_lit.value().as_str() {
+                        "start" => RelativeTo::Start,
+                        "current" => RelativeTo::Current,
+                        _ => return Err(meta.error("expected `\"start\"` or `\"current\"`")),
+                    };
+                    return Ok(());
+                }
+                if meta.path.is_ident("assert") {
+                    meta.input.parse::<Token![=]>()?;
+                    let expr_lit: LitStr = meta.input.parse()?;
+                    attrs.assert = Some(expr_lit.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("msg") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.msg = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("parse_with") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.parse_with = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("build_with") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.build_with = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("with") {
+                    meta.input.parse::<Token![=]>()?;
+                    let module: Path = meta.input.parse()?;
+                    attrs.parse_with = Some(syn::parse_quote!(#module::parse));
+                    attrs.build_with = Some(syn::parse_quote!(#module::build));
+                    return Ok(());
+                }
+                if meta.path.is_ident("wire") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.wire = Some(meta.input.parse()?);
+                    return Ok(());
+                }
+                for (key, fallible) in [("map", false), ("try_map", true)] {
+                    if meta.path.is_ident(key) {
+                        meta.input.parse::<Token![=]>()?;
+                        let expr_lit: LitStr = meta.input.parse()?;
+                        attrs.map = Some((expr_lit.parse()?, fallible));
+                        return Ok(());
+                    }
+                }
+                for (key, fallible) in [("unmap", false), ("try_unmap", true)] {
+                    if meta.path.is_ident(key) {
+                        meta.input.parse::<Token![=]>()?;
+                        let expr_lit: LitStr = meta.input.parse()?;
+                        attrs.unmap = Some((expr_lit.parse()?, fallible));
+                        return Ok(());
+                    }
+                }
+                if meta.path.is_ident("calc") {
+                    meta.input.parse::<Token![=]>()?;
+                    let expr_lit: LitStr = meta.input.parse()?;
+                    attrs.calc = Some(expr_lit.parse()?);
+                    return Ok(());
+                }
+                if meta.path.is_ident("verify_calc") {
+                    attrs.verify_calc = true;
+                    return Ok(());
+                }
+                if meta.path.is_ident("bits") {
+                    meta.input.parse::<Token![=]>()?;
+                    attrs.bits = Some(parse_nonzero(meta.input)?);
+                    return Ok(());
+                }
+                let key = meta.path.clone();
+                Err(meta.error(format!(
+                    "unknown option `{}`; expected `size_field = ...`, `size_func = ...`, `align = ...`, `sub_align = ...`, `sizes = [...]`, `aligns = [...]`, `ctx = \"...\"`, `cond = \"...\"`, `since = ...`, `until = ...`, `skip`, `default = \"...\"`, `pad_bits = ...`, `pad_bytes = ...`, `pad_bits_after = ...`, `pad_bytes_after = ...`, `offset_field = ...`, `seek = \"...\"`, `relative_to = \"...\"`, `assert = \"...\"`, `msg = \"...\"`, `parse_with = ...`, `build_with = ...`, `with = ...`, `wire = ...`, `map = \"...\"`, `try_map = \"...\"`, `unmap = \"...\"`, `try_unmap = \"...\"`, `calc = \"...\"`, `verify_calc`, or `bits = ...`",
+                    quote!(#key)
+                )))
+            })?;
+        }
+    }
+    Ok(attrs)
+}
+
+/// Parses an integer literal that must not be zero, such as an alignment or bit width.
+fn parse_nonzero(input: syn::parse::ParseStream) -> syn::Result<usize> {
+    let lit: LitInt = input.parse()?;
+    match lit.base10_parse()? {
+        0 => Err(syn::Error::new(lit.span(), "must be greater than zero")),
+        value => Ok(value),
+    }
+}
+
+fn parse_usize_list(input: syn::parse::ParseStream) -> syn::Result<Vec<usize>> {
+    let content;
+    syn::bracketed!(content in input);
+    let lits = content.parse_terminated(|input| input.parse::<LitInt>(), Token![,])?;
+    lits.iter().map(|lit| lit.base10_parse()).collect()
+}
+
+/// Conversions between the `wire` value and the field value.
+///
+/// `map` turns the parsed `val` into the field value; `unmap` produces `wire_val`
+/// from the field before it is built. `try_` variants may fail with any `Display` error.
+fn map_logic(
+    attrs: &FieldAttrs,
+    field_value: &TokenStream2,
+    name: &str,
+) -> (TokenStream2, TokenStream2) {
+    if attrs.wire.is_none() {
+        return (quote! {}, quote! {});
+    }
+    let map = match &attrs.map {
+        Some((map, false)) => quote! {
+            let val = shua_struct::__private::apply(val, #map);
+        },
+        Some((map, true)) => quote! {
+            let val = shua_struct::__private::apply(val, #map).map_err(|e| {
+                shua_struct::Error::new(shua_struct::__private::format!("{}", e))
+                    .in_field(#name, offset)
+            })?;
+        },
+        None => quote! {},
+    };
+    let unmap = match &attrs.unmap {
+        Some((unmap, false)) => quote! {
+            let wire_val = shua_struct::__private::apply(#field_value, #unmap);
+        },
+        Some((unmap, true)) => quote! {
+            let wire_val = shua_struct::__private::apply(#field_value, #unmap).map_err(|e| {
+                shua_struct::Error::new(shua_struct::__private::format!("{}", e))
+                    .in_field(#name, bv.len())
+            })?;
+        },
+        None => quote! {},
+    };
+    (map, unmap)
+}
+
+/// Fails with the field's `msg` at `bit` unless its `assert` expression holds.
+fn assert_logic(attrs: &FieldAttrs, name: &str, bit: TokenStream2) -> TokenStream2 {
+    let Some(assert) = &attrs.assert else {
+        return quote! {};
+    };
+    let msg = match &attrs.msg {
+        Some(msg) => msg.value(),
+        None => format!("assertion failed: {}", quote!(#assert)),
+    };
+    quote! {
+        if !(#assert) {
+            return Err(shua_struct::Error::new(#msg).in_field(#name, #bit));
+        }
+    }
+}
+
+/// Fields named as `self.field` in `tokens`, leaving out method calls.
+fn self_fields(tokens: TokenStream2) -> Vec<Ident> {
+    let mut names = Vec::new();
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        if let TokenTree::Group(group) = token {
+            names.extend(self_fields(group.stream()));
+            continue;
+        }
+        let is_call = matches!(
+            tokens.get(i + 3),
+            Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis
+        );
+        if let (TokenTree::Ident(this), Some(TokenTree::Punct(dot)), Some(TokenTree::Ident(name))) =
+            (token, tokens.get(i + 1), tokens.get(i + 2))
+            && this == "self"
+            && dot.as_char() == '.'
+            && !is_call
+        {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+/// Local holding the build position of a field whose value is back-filled.
+fn start_var(field_name: &Ident) -> Ident {
+    format_ident!("__shua_start_{}", field_name)
+}
+
+/// Parse and build statements for `pad` bits of zero padding.
+fn pad_logic(pad: usize) -> (TokenStream2, TokenStream2) {
+    if pad == 0 {
+        return (quote! {}, quote! {});
+    }
+    (
+        quote! { offset += #pad; },
+        quote! { bv.resize(bv.len() + #pad, false); },
+    )
+}
+
+/// Whether a field is on the wire; fields that are not take their `default` (or `Default`) value on parse.
+///
+/// `since`/`until` compare the struct's `version` against a half-open range `since..until`.
+fn field_cond(attrs: &FieldAttrs, version: Option<&Expr>) -> TokenStream2 {
+    let mut conds = Vec::new();
+    if let Some(cond) = &attrs.cond {
+        conds.push(quote! { (#cond) });
+    }
+    if let Some(version) = version {
+        if let Some(since) = &attrs.since {
+            conds.push(quote! { #version >= #since });
+        }
+        if let Some(until) = &attrs.until {
+            conds.push(quote! { #version < #until });
+        }
+    }
+    if conds.is_empty() {
+        quote! { true }
+    } else {
+        quote! { #(#conds)&&* }
+    }
+}
+
+/// Binds `ctx` to the struct's declared context type, taken from `outer_opts`.
+fn ctx_binding(struct_name: &Ident, ctx: Option<&Type>, action: &str) -> TokenStream2 {
+    let Some(ctx) = ctx else {
+        return quote! {};
+    };
+    let msg = format!(
+        "{} {} error: missing ctx `{}`",
+        struct_name,
+        action,
+        quote!(#ctx).to_string().replace(' ', "")
+    );
+    quote! {
+        let ctx: &#ctx = outer_opts
+            .as_ref()
+            .and_then(|opts| opts.ctx::<#ctx>())
+            .ok_or(#msg)?;
+    }
+}
+
+/// Builds the `Option<Options>` expression for a field; a size source sets the level 0 `count`.
+fn field_opts(attrs: &FieldAttrs) -> TokenStream2 {
+    let size = if let Some(size_field) = &attrs.size_field {
+        Some(quote! { self.#size_field.into() })
+    } else {
+        attrs
+            .size_func
+            .as_ref()
+            .map(|size_func| quote! { self.#size_func() })
+    };
+    if size.is_none()
+        && attrs.sizes.is_empty()
+        && attrs.aligns.is_empty()
+        && attrs.ctx.is_none()
+        && attrs.bits.is_none()
+    {
+        return quote! { None };
+    }
+    let sizes = &attrs.sizes;
+    let count = match size {
+        Some(size) => quote! { Some(#size) },
+        None => quote! { None },
+    };
+    let aligns = &attrs.aligns;
+    let bits = match attrs.bits {
+        Some(bits) => quote! { Some(#bits) },
+        None => quote! { None },
+    };
+    let ctx = match &attrs.ctx {
+        Some(ctx) => quote! {
+            Some(shua_struct::__private::Rc::new(#ctx) as shua_struct::__private::Rc<dyn shua_struct::__private::Any>)
+        },
+        None => quote! { None },
+    };
+    quote! {
+        Some(shua_struct::Options {
+            sizes: shua_struct::__private::Vec::from([#(#sizes),*]),
+            count: #count,
+            aligns: shua_struct::__private::Vec::from([#(#aligns),*]),
+            ctx: #ctx,
+            bits: #bits,
+        })
+    }
+}
+
+/// The `///` doc comment in `attrs`, one line per doc attribute.
+fn doc_string(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value()),
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Renders a type or expression as Rust source with `quote`'s token spacing tidied up.
+fn source_string(tokens: impl ToTokens) -> String {
+    let raw = tokens.to_token_stream().to_string();
+    let is_type = syn::parse_str::<Type>(&raw).is_ok();
+    let chars: Vec<char> = raw.chars().collect();
+    let mut out = String::with_capacity(raw.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let prev = out.chars().last().unwrap_or(' ');
+            let next = chars.get(i + 1).copied().unwrap_or(' ');
+            let word = prev.is_alphanumeric() || prev == '_';
+            let path_sep = next == ':' && chars.get(i + 2) == Some(&':') || out.ends_with("::");
+            let tight = matches!(next, '.' | ',' | ';' | ')' | ']' | '?')
+                || matches!(prev, '.' | '(' | '[')
+                || (matches!(next, '(' | '[') && word)
+                || path_sep
+                || (is_type && (matches!(next, '<' | '>') || matches!(prev, '<' | '&')));
+            if tight {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Builds the `FieldSchema` expression describing one field.
+fn field_schema(
+    field: &Field,
+    attrs: &FieldAttrs,
+    field_align: Option<usize>,
+    bit_order: &Path,
+) -> TokenStream2 {
+    let name = field
+        .ident
+        .as_ref()
+        .map(Ident::to_string)
+        .unwrap_or_default();
+    let doc = doc_string(&field.attrs);
+    let field_type = &field.ty;
+    let rust_type = source_string(field_type);
+    let some_string = |value: Option<String>| match value {
+        Some(value) => quote! { Some(#value.into()) },
+        None => quote! { None },
+    };
+    let some_usize = |value: Option<usize>| match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    };
+    let wire_type = some_string(attrs.wire.as_ref().map(source_string));
+    let layout_type = attrs.wire.as_ref().unwrap_or(field_type);
+    let layout = if attrs.skip || attrs.parse_with.is_some() || attrs.build_with.is_some() {
+        quote! { shua_struct::Layout::Opaque }
+    } else {
+        quote! { <#layout_type as shua_struct::BinaryField<#bit_order>>::layout() }
+    };
+    let bits = some_usize(attrs.bits);
+    let align = some_usize(field_align);
+    let aligns = &attrs.aligns;
+    let sizes = &attrs.sizes;
+    let size = match (&attrs.size_field, &attrs.size_func) {
+        (Some(size_field), _) => {
+            let size_field = size_field.to_string();
+            quote! { shua_struct::schema::SizeSource::Field(#size_field.into()) }
+        }
+        (None, Some(size_func)) => {
+            let size_func = size_func.to_string();
+            quote! { shua_struct::schema::SizeSource::Func(#size_func.into()) }
+        }
+        (None, None) => quote! { shua_struct::schema::SizeSource::None },
+    };
+    let pad_before = attrs.pad_before;
+    let pad_after = attrs.pad_after;
+    let skip = attrs.skip;
+    let cond = some_string(attrs.cond.as_ref().map(source_string));
+    let since = some_string(attrs.since.as_ref().map(LitInt::to_string));
+    let until = some_string(attrs.until.as_ref().map(LitInt::to_string));
+    let relative = match attrs.relative_to {
+        RelativeTo::Start => quote! { shua_struct::schema::Relative::Start },
+        RelativeTo::Current => quote! { shua_struct::schema::Relative::Current },
+    };
+    let position = match (&attrs.offset_field, &attrs.seek) {
+        (Some(offset_field), _) => {
+            let offset_field = offset_field.to_string();
+            quote! {
+                shua_struct::schema::Position::OffsetField {
+                    field: #offset_field.into(),
+                    relative: #relative,
+                }
+            }
+        }
+        (None, Some(seek)) => {
+            let seek = source_string(seek);
+            quote! {
+                shua_struct::schema::Position::Seek {
+                    expr: #seek.into(),
+                    relative: #relative,
+                }
+            }
+        }
+        (None, None) => quote! { shua_struct::schema::Position::Sequential },
+    };
+    let calc = some_string(attrs.calc.as_ref().map(source_string));
+    quote! {
+        shua_struct::schema::FieldSchema {
+            name: #name.into(),
+            doc: #doc.into(),
+            rust_type: #rust_type.into(),
+            wire_type: #wire_type,
+            layout: #layout,
+            bits: #bits,
+            align: #align,
+            aligns: shua_struct::__private::Vec::from([#(#aligns),*]),
+            sizes: shua_struct::__private::Vec::from([#(#sizes),*]),
+            size: #size,
+            pad_before: #pad_before,
+            pad_after: #pad_after,
+            presence: shua_struct::schema::Presence {
+                skip: #skip,
+                cond: #cond,
+                since: #since,
+                until: #until,
+            },
+            position: #position,
+            calc: #calc,
+        }
+    }
 }